@@ -0,0 +1,151 @@
+//! Key bindings: which key events do what. The table is in the config
+//! file under [keys], the defaults below are what the keys have always
+//! done.
+//!
+//! bindings.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 22-Jul-2025
+//!
+
+use crate::defs::*;
+use crate::input::*;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    BacklightOn,
+    BacklightOff,
+    BacklightToggle,
+    BacklightStep,
+    NextPage,
+    PrevPage,
+    ScrollUp,
+    ScrollDown,
+    Select,
+    Flip,
+    CryptoRefresh,
+    Reboot,   // asks first
+    Shutdown, // asks first
+    IpQr,
+    Command(String), // sh -c, not waited for
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Binding {
+    pub key: Key,
+    #[serde(default = "press")]
+    pub on: KeyEventKind,
+    pub action: Action,
+}
+
+fn press() -> KeyEventKind {
+    KeyEventKind::Press
+}
+
+/// What the keys did before they could be bound
+pub fn default_bindings() -> Vec<Binding> {
+    use Action::*;
+    use KeyEventKind::{Press, Repeat};
+
+    [
+        (Key::Key1, Press, BacklightToggle),
+        (Key::Key2, Press, BacklightStep),
+        (Key::Key2, Repeat, BacklightStep),
+        (Key::Key3, Press, Flip),
+        (Key::Left, Press, PrevPage),
+        (Key::Left, Repeat, PrevPage),
+        (Key::Right, Press, NextPage),
+        (Key::Right, Repeat, NextPage),
+        (Key::Up, Press, ScrollUp),
+        (Key::Up, Repeat, ScrollUp),
+        (Key::Down, Press, ScrollDown),
+        (Key::Down, Repeat, ScrollDown),
+        (Key::Press, Press, Select),
+    ]
+    .into_iter()
+    .map(|(key, on, action)| Binding { key, on, action })
+    .collect()
+}
+
+/// Actions bound to `event`, in table order
+pub fn bound_actions(bindings: &[Binding], event: KeyEvent) -> impl Iterator<Item = &Action> {
+    bindings
+        .iter()
+        .filter(move |b| b.key == event.key && b.on == event.kind)
+        .map(|b| &b.action)
+}
+
+/// Duplicates, empty commands and power actions that can't be
+/// confirmed, one message per problem
+pub fn validate_bindings(bindings: &[Binding], errors: &mut Vec<String>) {
+    let can_confirm = bindings.iter().any(|b| b.action == Action::Select);
+    for (i, binding) in bindings.iter().enumerate() {
+        if matches!(binding.action, Action::Reboot | Action::Shutdown) && !can_confirm {
+            errors.push(format!(
+                "keys.bindings[{}]: {:?} needs a key bound to select to confirm it",
+                i, binding.action
+            ));
+        }
+        if let Action::Command(command) = &binding.action
+            && command.trim().is_empty()
+        {
+            errors.push(format!("keys.bindings[{}]: command must not be empty", i));
+        }
+        if bindings[..i].contains(binding) {
+            errors.push(format!(
+                "keys.bindings[{}]: {:?} on {:?} is bound to {:?} twice",
+                i, binding.key, binding.on, binding.action
+            ));
+        }
+    }
+}
+
+/// Run `command` with sh in the background, its exit status is logged
+pub fn run_command(command: &str) {
+    info!("{}(): {}", func_name!(), command);
+    match Command::new("sh").arg("-c").arg(command).spawn() {
+        Ok(mut child) => {
+            let command = command.to_string();
+            thread::spawn(move || match child.wait() {
+                Ok(status) if status.success() => debug!("{}: {}", command, status),
+                Ok(status) => warn!("{}: {}", command, status),
+                Err(e) => error!("{}: {}", command, e),
+            });
+        }
+        Err(e) => error!("{}(): {}: {}", func_name!(), command, e),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Power {
+    Reboot,
+    Shutdown,
+}
+
+impl Power {
+    pub fn verb(self) -> &'static str {
+        match self {
+            Power::Reboot => "reboot",
+            Power::Shutdown => "shut down",
+        }
+    }
+
+    /// Hand over to systemd
+    pub fn run(self) {
+        let arg = match self {
+            Power::Reboot => "reboot",
+            Power::Shutdown => "poweroff",
+        };
+        info!("{}(): systemctl {}", func_name!(), arg);
+        match Command::new("systemctl").arg(arg).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => error!("{}(): systemctl {}: {}", func_name!(), arg, status),
+            Err(e) => error!("{}(): systemctl {}: {}", func_name!(), arg, e),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Display transport abstraction. `Lcd` talks to the panel only through
+//! the `DisplayBus` trait, so the render path can run without a Raspberry Pi.
+//!
+//! bus.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 01-Jul-2025
+//!
+
+use crate::defs::*;
+use crate::gpio::*;
+use crate::lcd::lcd::*;
+use crate::spi::*;
+use log::{LevelFilter, debug, error, info, warn};
+use rppal::gpio::{Gpio, Level, OutputPin};
+use rppal::spi::Spi;
+use std::error::Error;
+
+/// Control lines of the LCD HAT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LcdPin {
+    Cs,
+    Dc,
+    Rst,
+    Bl,
+}
+
+/// Everything `Lcd` needs from the hardware: command and data writes,
+/// control line levels and delays.
+pub trait DisplayBus {
+    /// Write a command byte (DC low)
+    fn write_cmd(&mut self, cmd: UBYTE) -> Result<(), LcdError>;
+
+    /// Write parameter or pixel bytes (DC high)
+    fn write_data(&mut self, data: &[UBYTE]) -> Result<(), LcdError>;
+
+    /// Command followed by all of its parameters in one data write
+    fn write_cmd_data(&mut self, cmd: UBYTE, params: &[UBYTE]) -> Result<(), LcdError> {
+        self.write_cmd(cmd)?;
+        if !params.is_empty() {
+            self.write_data(params)?;
+        }
+        Ok(())
+    }
+
+    /// Drive one of the DC/CS/RST/BL lines
+    fn set_pin(&mut self, pin: LcdPin, level: Level) -> Result<(), LcdError>;
+
+    fn delay_ms(&mut self, ms: u64);
+}
+
+/// The real thing, SPI0 and GPIO on the Raspberry Pi via rppal. The SPI
+/// handle and the DC/RST pins are opened once and kept. CS and the
+/// backlight pin are only borrowed while switching them: rppal gives CS
+/// back to SPI0 (ALT0) so spidev drives it for the transfers, and
+/// `bl_pwm()` owns the backlight pin afterwards.
+pub struct RppalBus {
+    spi: Spi,
+    cs_pin: UBYTE,
+    dc_pin: OutputPin,  // Data / Command - 0=WriteCommand, 1=WriteData
+    rst_pin: OutputPin, // Reset
+    bl_pin: UBYTE,      // Backlight PWM
+    dc_level: Level,
+}
+
+impl RppalBus {
+    pub fn new(
+        cs_pin: UBYTE,
+        dc_pin: UBYTE,
+        rst_pin: UBYTE,
+        bl_pin: UBYTE,
+    ) -> Result<Self, Box<dyn Error>> {
+        let gpio = Gpio::new()?;
+        let mut dc = gpio.get(dc_pin)?.into_output();
+        dc.set_low();
+
+        Ok(Self {
+            spi: get_spi()?,
+            cs_pin,
+            dc_pin: dc,
+            rst_pin: gpio.get(rst_pin)?.into_output(),
+            bl_pin,
+            dc_level: Level::Low,
+        })
+    }
+
+    fn set_dc(&mut self, level: Level) {
+        if self.dc_level != level {
+            self.dc_pin.write(level);
+            self.dc_level = level;
+        }
+    }
+
+    fn spi_write(&mut self, data: &[UBYTE]) -> Result<(), LcdError> {
+        spi_write_chunked(&mut self.spi, data).map_err(|e| {
+            error!("{}(): {:?}", func_name!(), e);
+            LcdError::SpiError
+        })
+    }
+}
+
+impl DisplayBus for RppalBus {
+    fn write_cmd(&mut self, cmd: UBYTE) -> Result<(), LcdError> {
+        self.set_dc(Level::Low);
+        self.spi_write(&[cmd])
+    }
+
+    fn write_data(&mut self, data: &[UBYTE]) -> Result<(), LcdError> {
+        self.set_dc(Level::High);
+        self.spi_write(data)
+    }
+
+    fn set_pin(&mut self, pin: LcdPin, level: Level) -> Result<(), LcdError> {
+        match pin {
+            LcdPin::Cs => return write_borrowed(self.cs_pin, level),
+            LcdPin::Dc => self.set_dc(level),
+            LcdPin::Rst => self.rst_pin.write(level),
+            LcdPin::Bl => return write_borrowed(self.bl_pin, level),
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u64) {
+        gpio_sleep_ms(ms);
+    }
+}
+
+// The pin goes back to its old mode when rppal drops it
+fn write_borrowed(pin: UBYTE, level: Level) -> Result<(), LcdError> {
+    gpio_write2(pin, level).map_err(|e| {
+        error!("{}(): {:?}", func_name!(), e);
+        LcdError::PinError
+    })
+}
+
+/// One recorded bus transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum BusOp {
+    Cmd(UBYTE),
+    Data(Vec<UBYTE>),
+    Pin(LcdPin, Level),
+    Delay(u64),
+}
+
+/// In-memory backend, records every transaction instead of touching
+/// hardware. Delays are recorded, not slept.
+#[derive(Default)]
+pub struct RecordingBus {
+    ops: Vec<BusOp>,
+}
+
+impl RecordingBus {
+    pub fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    pub fn ops(&self) -> &[BusOp] {
+        &self.ops
+    }
+
+    pub fn take_ops(&mut self) -> Vec<BusOp> {
+        std::mem::take(&mut self.ops)
+    }
+
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// Total number of data bytes written so far
+    pub fn data_len(&self) -> usize {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                BusOp::Data(d) => d.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl DisplayBus for RecordingBus {
+    fn write_cmd(&mut self, cmd: UBYTE) -> Result<(), LcdError> {
+        self.ops.push(BusOp::Cmd(cmd));
+        Ok(())
+    }
+
+    fn write_data(&mut self, data: &[UBYTE]) -> Result<(), LcdError> {
+        self.ops.push(BusOp::Data(data.to_vec()));
+        Ok(())
+    }
+
+    fn set_pin(&mut self, pin: LcdPin, level: Level) -> Result<(), LcdError> {
+        self.ops.push(BusOp::Pin(pin, level));
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, ms: u64) {
+        self.ops.push(BusOp::Delay(ms));
+    }
+}
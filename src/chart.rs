@@ -0,0 +1,213 @@
+//! Chart widget for the stats history: line, bar and sparkline styles,
+//! auto-scaled to the data, with min/max labels.
+//!
+//! Values are oldest first and right-aligned, the newest sample always
+//! sits on the right edge. More samples than columns are averaged down.
+//!
+//! chart.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 13-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::fonts::font8::*;
+use crate::lcd::lcd::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartStyle {
+    #[default]
+    Line,
+    Bar,
+    Sparkline, // line only, no labels or axis
+}
+
+#[derive(Debug, Clone)]
+pub struct Chart {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    style: ChartStyle,
+    colour: UWORD,
+    bg: UWORD,
+    axis_colour: UWORD,
+    label_colour: UWORD,
+    range: Option<(f32, f32)>,
+}
+
+impl Chart {
+    pub fn new(x: usize, y: usize, w: usize, h: usize) -> Self {
+        Self {
+            x,
+            y,
+            w,
+            h,
+            style: ChartStyle::Line,
+            colour: GREEN,
+            bg: BLACK,
+            axis_colour: GRAY,
+            label_colour: BLUE2,
+            range: None,
+        }
+    }
+
+    pub fn with_style(mut self, style: ChartStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_colours(mut self, colour: UWORD, bg: UWORD) -> Self {
+        self.colour = colour;
+        self.bg = bg;
+        self
+    }
+
+    pub fn with_label_colours(mut self, axis: UWORD, label: UWORD) -> Self {
+        self.axis_colour = axis;
+        self.label_colour = label;
+        self
+    }
+
+    /// Fixed scale instead of fitting the data
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Clear the chart area and draw `values`, labels formatted with `format`
+    pub fn draw<B: DisplayBus>(
+        &self,
+        l: &mut Lcd<B>,
+        values: &[Option<f32>],
+        format: impl Fn(f32) -> String,
+    ) {
+        l.img_draw_rect(self.x, self.y, self.w, self.h, self.bg);
+
+        // Plot area, less a label row above and below
+        let labels = self.style != ChartStyle::Sparkline;
+        let label_h = if labels { FONT8.height + 1 } else { 0 };
+        if self.w == 0 || self.h <= 2 * label_h + 1 {
+            return;
+        }
+        let (left, top) = (self.x as i32, (self.y + label_h) as i32);
+        let (pw, ph) = (self.w as i32, (self.h - 2 * label_h) as i32);
+
+        let columns = bucket(values, self.w);
+        let Some((min, max)) = self.range.or_else(|| scale(&columns)) else {
+            if labels {
+                l.img_draw_string(
+                    &(self.x + 2),
+                    &(self.y + self.h / 2 - FONT8.height / 2),
+                    "No data",
+                    &FONT8,
+                    self.label_colour,
+                    self.bg,
+                );
+            }
+            return;
+        };
+
+        let y_of = |v: f32| {
+            let t = ((v - min) / (max - min)).clamp(0.0, 1.0);
+            top + ph - 1 - (t * (ph - 1) as f32).round() as i32
+        };
+        let cw = (pw / columns.len().max(1) as i32).max(1);
+        let x_of = |c: usize| left + pw - (columns.len() - c) as i32 * cw;
+
+        if labels {
+            l.img_draw_line(
+                left,
+                top + ph - 1,
+                left + pw - 1,
+                top + ph - 1,
+                self.axis_colour,
+            );
+        }
+
+        match self.style {
+            ChartStyle::Bar => {
+                for (c, v) in columns.iter().enumerate() {
+                    if let Some(v) = v {
+                        let y = y_of(*v);
+                        let w = if cw > 2 { cw - 1 } else { cw };
+                        l.img_draw_rect(
+                            x_of(c) as usize,
+                            y as usize,
+                            w as usize,
+                            (top + ph - y) as usize,
+                            self.colour,
+                        );
+                    }
+                }
+            }
+            ChartStyle::Line | ChartStyle::Sparkline => {
+                let mut last: Option<(i32, i32)> = None;
+                for (c, v) in columns.iter().enumerate() {
+                    let Some(v) = v else {
+                        last = None; // gap in the data, break the line
+                        continue;
+                    };
+                    let point = (x_of(c) + cw / 2, y_of(*v));
+                    let (x0, y0) = last.unwrap_or(point);
+                    l.img_draw_line(x0, y0, point.0, point.1, self.colour);
+                    last = Some(point);
+                }
+                if self.style == ChartStyle::Sparkline
+                    && let Some((x, y)) = last
+                {
+                    l.img_fill_circle(x, y, 1, self.label_colour);
+                }
+            }
+        }
+
+        if labels {
+            l.img_draw_string(
+                &self.x,
+                &self.y,
+                &format!("max {}", format(max)),
+                &FONT8,
+                self.label_colour,
+                self.bg,
+            );
+            l.img_draw_string(
+                &self.x,
+                &(self.y + self.h - FONT8.height),
+                &format!("min {}", format(min)),
+                &FONT8,
+                self.label_colour,
+                self.bg,
+            );
+        }
+    }
+}
+
+// Average `values` down to at most `columns` entries, a column is None
+// if none of its samples are known
+fn bucket(values: &[Option<f32>], columns: usize) -> Vec<Option<f32>> {
+    if values.len() <= columns {
+        return values.to_vec();
+    }
+    (0..columns)
+        .map(|c| {
+            let bucket = &values[c * values.len() / columns..(c + 1) * values.len() / columns];
+            let known: Vec<f32> = bucket.iter().flatten().copied().collect();
+            (!known.is_empty()).then(|| known.iter().sum::<f32>() / known.len() as f32)
+        })
+        .collect()
+}
+
+// Lowest and highest value, padded apart if they are the same
+fn scale(values: &[Option<f32>]) -> Option<(f32, f32)> {
+    let known = values.iter().flatten();
+    let min = known.clone().copied().reduce(f32::min)?;
+    let max = known.copied().reduce(f32::max)?;
+    if max <= min {
+        let pad = (min.abs() * 0.1).max(1.0);
+        Some((min - pad, max + pad))
+    } else {
+        Some((min, max))
+    }
+}
@@ -0,0 +1,123 @@
+//! Command line interface and logger set-up
+//!
+//! cli.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 10-Jul-2025
+//!
+
+use crate::pages::Page;
+use chrono::Local;
+use clap::{Parser, Subcommand};
+use log::{LevelFilter, Log, Metadata, Record, debug, error, info, warn};
+use std::path::PathBuf;
+use systemd_journal_logger::JournalLog;
+
+/// Status display for the Waveshare LCD HATs
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Config file, instead of /etc/LCD/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// off, error, warn, info, debug or trace
+    #[arg(long, global = true, value_name = "LEVEL", default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// Log to stderr instead of the systemd journal
+    #[arg(long, global = true)]
+    pub stderr_log: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Drive the display, the default
+    Run,
+    /// Render one frame with the simulated panel and save it
+    Render {
+        /// PNG, or PPM if the name ends in .ppm
+        #[arg(long, default_value = "frame.png")]
+        out: PathBuf,
+        /// Page to render, instead of the one shown at startup
+        #[arg(long, value_enum)]
+        page: Option<Page>,
+    },
+    /// Print the statistics once
+    Stats {
+        /// As the JSON sent to remote displays
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the backlight schedule's transitions
+    Schedule {
+        /// How many days ahead, from today
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+    },
+    /// List serial ports, marking the ones that match the USB config
+    UsbProbe,
+    /// Show colour bars and gradients on the panel
+    TestPattern {
+        /// Save to this file with the simulated panel instead
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// How long to keep the pattern on the panel
+        #[arg(long, default_value_t = 10)]
+        secs: u64,
+    },
+}
+
+struct StderrLog;
+
+impl Log for StderrLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "{} {:<5} {}",
+                Local::now().format("%H:%M:%S%.3f"),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static STDERR_LOG: StderrLog = StderrLog;
+
+/// Journal logger, or stderr if asked for or if there is no journal
+pub fn init_logging(level: LevelFilter, stderr: bool) {
+    let journal = if stderr {
+        None
+    } else {
+        match JournalLog::new() {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                eprintln!("No systemd journal ({}), logging to stderr", e);
+                None
+            }
+        }
+    };
+
+    match journal {
+        Some(journal) => journal
+            .with_extra_fields(vec![("VERSION", env!("CARGO_PKG_VERSION"))])
+            .with_syslog_identifier(
+                systemd_journal_logger::current_exe_identifier()
+                    .expect("Error: systemd_journal_logger::current_exe_identifier()")
+                    .to_string(),
+            )
+            .install()
+            .unwrap(),
+        None => log::set_logger(&STDERR_LOG).unwrap(),
+    }
+    log::set_max_level(level);
+}
@@ -0,0 +1,692 @@
+//! Runtime configuration, loaded from a TOML file. Anything missing from
+//! the file falls back to the defaults in defs.rs. SIGHUP re-reads it,
+//! the threads pick up their part of the new config as they loop.
+//!
+//! config.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 08-Jul-2025
+//!
+
+use crate::bindings::*;
+use crate::chart::*;
+use crate::defs::*;
+use crate::fontfile::*;
+use crate::history::*;
+use crate::idle::*;
+use crate::layout::*;
+use crate::lcd::lcd::*;
+use crate::pages::*;
+use crate::panel::*;
+use crate::schedule::*;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const CONFIG_PATH: &str = "/etc/LCD/config.toml";
+
+/// Config shared with the threads, replaced as a whole on reload
+pub type SharedConfig = Arc<Mutex<Config>>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub screen: ScreenConfig,
+    pub fonts: BTreeMap<String, PathBuf>, // font files for layouts, by name
+    pub pages: PagesConfig,
+    pub backlight: BacklightConfig,
+    pub schedule: ScheduleConfig,
+    pub idle: IdleConfig,
+    pub input: InputConfig,
+    pub keys: KeysConfig,
+    pub http: HttpConfig,
+    pub crypto: CryptoConfig,
+    pub usb: UsbConfig,
+    pub pins: PinConfig,
+    pub history: HistoryConfig,
+    pub chart: ChartConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreenConfig {
+    pub panel: String,
+    pub orientation: Option<u16>, // degrees, panel's upright orientation if not set
+    pub flip: bool,
+    pub update_interval_secs: u64,
+    pub view: ScreenView,
+    pub layout: Option<PathBuf>, // dashboard layout file, built-in if not set
+}
+
+/// The page shown at startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenView {
+    #[default]
+    Dashboard,
+    Chart,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PagesConfig {
+    pub list: Vec<Page>,  // in joystick order
+    pub rotate_secs: u64, // 0 to stay put
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BacklightConfig {
+    pub on_percent: u8,
+    pub step_percent: u8,
+    pub gamma: f32,
+    pub fade_ms: u64, // 0 to switch at once
+    pub pwm_hz: u32,
+}
+
+/// Backlight levels through the day, sunrise and sunset need the
+/// latitude and longitude (north and east positive)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    pub rules: Vec<Rule>, // empty for no schedule
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// Idle timeout: dim after dim_secs without a key, off off_secs after that
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdleConfig {
+    pub dim_secs: u64, // 0 to not dim
+    pub dim_percent: u8,
+    pub off_secs: u64,      // 0 to stay on
+    pub alerts: Vec<Alert>, // any of these wakes the panel
+}
+
+/// Key timing, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputConfig {
+    pub debounce_ms: u64,
+    pub long_press_ms: u64,
+    pub repeat_delay_ms: u64,
+    pub repeat_interval_ms: u64, // 0 for no repeats
+    pub double_click_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub bindings: Vec<Binding>, // replaces the defaults as a whole
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CryptoConfig {
+    pub btc_cmp_url: String,
+    pub btc_ath_url: String,
+    pub req_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UsbConfig {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub serial_num: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub sample_interval_secs: u64,
+    pub length_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChartConfig {
+    pub metric: Metric,
+    pub style: ChartStyle,
+}
+
+/// BCM GPIO numbers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PinConfig {
+    pub lcd_cs: UBYTE,
+    pub lcd_rst: UBYTE,
+    pub lcd_dc: UBYTE,
+    pub lcd_bl: UBYTE,
+    pub key_up: UBYTE,
+    pub key_down: UBYTE,
+    pub key_left: UBYTE,
+    pub key_right: UBYTE,
+    pub key_press: UBYTE,
+    pub key1: UBYTE,
+    pub key2: UBYTE,
+    pub key3: UBYTE,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "can't read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "can't parse {}: {}", path.display(), e),
+            ConfigError::Invalid(errors) => write!(f, "invalid config: {}", errors.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for ScreenConfig {
+    fn default() -> Self {
+        Self {
+            panel: LCD_PANEL.to_string(),
+            orientation: None,
+            flip: LCD_FLIP,
+            update_interval_secs: SCREEN_UPDATE_INTERVAL_SECS,
+            view: ScreenView::Dashboard,
+            layout: None,
+        }
+    }
+}
+
+impl Default for PagesConfig {
+    fn default() -> Self {
+        Self {
+            list: DEFAULT_PAGES.to_vec(),
+            rotate_secs: PAGES_ROTATE_SECS,
+        }
+    }
+}
+
+impl Default for BacklightConfig {
+    fn default() -> Self {
+        Self {
+            on_percent: BACKLIGHT_ON_PERCENT,
+            step_percent: BACKLIGHT_STEP_PERCENT,
+            gamma: BACKLIGHT_GAMMA,
+            fade_ms: BACKLIGHT_FADE_MS,
+            pwm_hz: BACKLIGHT_PWM_HZ,
+        }
+    }
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            latitude: None,
+            longitude: None,
+        }
+    }
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            dim_secs: IDLE_DIM_SECS,
+            dim_percent: IDLE_DIM_PERCENT,
+            off_secs: IDLE_OFF_SECS,
+            alerts: default_alerts(),
+        }
+    }
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: INPUT_DEBOUNCE_MS,
+            long_press_ms: INPUT_LONG_PRESS_MS,
+            repeat_delay_ms: INPUT_REPEAT_DELAY_MS,
+            repeat_interval_ms: INPUT_REPEAT_INTERVAL_MS,
+            double_click_ms: INPUT_DOUBLE_CLICK_MS,
+        }
+    }
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self {
+            bindings: default_bindings(),
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            host: HTTP_HOST.to_string(),
+            port: HTTP_PORT,
+        }
+    }
+}
+
+impl Default for CryptoConfig {
+    fn default() -> Self {
+        Self {
+            btc_cmp_url: HTTP_BTC_CMP_URL.to_string(),
+            btc_ath_url: HTTP_BTC_ATH_URL.to_string(),
+            req_interval_secs: HTTP_CRYPTO_REQ_INTERVAL_SECS,
+        }
+    }
+}
+
+impl Default for UsbConfig {
+    fn default() -> Self {
+        Self {
+            vendor_id: USB_DEV_VENDOR_ID,
+            product_id: USB_DEV_PRODUCT_ID,
+            serial_num: USB_DEV_SERIAL_NUM.to_string(),
+        }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval_secs: HISTORY_SAMPLE_INTERVAL_SECS,
+            length_secs: HISTORY_LENGTH_SECS,
+        }
+    }
+}
+
+impl Default for ChartConfig {
+    fn default() -> Self {
+        Self {
+            metric: Metric::CpuTemp,
+            style: ChartStyle::Line,
+        }
+    }
+}
+
+impl Default for PinConfig {
+    fn default() -> Self {
+        Self {
+            lcd_cs: LCD_CS,
+            lcd_rst: LCD_RST,
+            lcd_dc: LCD_DC,
+            lcd_bl: LCD_BL,
+            key_up: KEY_UP,
+            key_down: KEY_DOWN,
+            key_left: KEY_LEFT,
+            key_right: KEY_RIGHT,
+            key_press: KEY_PRESS,
+            key1: KEY1,
+            key2: KEY2,
+            key3: KEY3,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml(path: &Path, text: &str) -> Result<Self, ConfigError> {
+        let config: Config =
+            toml::from_str(text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        Self::from_toml(path, &text)
+    }
+
+    /// Load `path` if given, it must exist. Otherwise CONFIG_PATH, or the
+    /// built-in defaults if there is no file there.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self, ConfigError> {
+        match path {
+            Some(path) => Self::load(path),
+            None if Path::new(CONFIG_PATH).exists() => Self::load(Path::new(CONFIG_PATH)),
+            None => {
+                info!(
+                    "{}(): {} not found, using defaults",
+                    func_name!(),
+                    CONFIG_PATH
+                );
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// Check everything that can be checked before touching hardware,
+    /// all problems are reported together.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors: Vec<String> = Vec::new();
+
+        if panel_profile(&self.screen.panel).is_none() {
+            errors.push(format!(
+                "screen.panel: unknown panel \"{}\", expected one of {:?}",
+                self.screen.panel,
+                panel_names()
+            ));
+        }
+        if let Some(degrees) = self.screen.orientation
+            && (degrees >= 360 || LcdOrientation::from_degrees(degrees).is_none())
+        {
+            errors.push(format!(
+                "screen.orientation: {} is not one of 0, 90, 180, 270",
+                degrees
+            ));
+        }
+        if self.screen.update_interval_secs == 0 {
+            errors.push("screen.update_interval_secs: must be at least 1".to_string());
+        }
+        for (name, path) in &self.fonts {
+            if !matches!(FontName::from(name.clone()), FontName::File(_)) {
+                errors.push(format!("fonts.{}: is the name of a built-in font", name));
+            } else if let Err(e) = BitmapFont::load(path) {
+                errors.push(format!("fonts.{}: {}", name, e));
+            }
+        }
+        if let Some(path) = &self.screen.layout
+            && let Err(e) = Layout::load(path, &self.fonts)
+        {
+            errors.push(format!("screen.layout: {}", e));
+        }
+
+        if self.pages.list.is_empty() {
+            errors.push("pages.list: must have at least one page".to_string());
+        }
+        for (i, page) in self.pages.list.iter().enumerate() {
+            if self.pages.list[..i].contains(page) {
+                errors.push(format!("pages.list: {:?} is listed twice", page));
+            }
+        }
+        if !self.pages.list.contains(&Page::from(self.screen.view)) {
+            errors.push(format!(
+                "screen.view: the {:?} page is not in pages.list",
+                Page::from(self.screen.view)
+            ));
+        }
+
+        let bl = &self.backlight;
+        for (key, percent) in [
+            ("backlight.on_percent", bl.on_percent),
+            ("backlight.step_percent", bl.step_percent),
+        ] {
+            if !(1..=100).contains(&percent) {
+                errors.push(format!("{}: {} is not 1 to 100", key, percent));
+            }
+        }
+        if !(1.0..=3.0).contains(&bl.gamma) {
+            errors.push(format!("backlight.gamma: {} is not 1.0 to 3.0", bl.gamma));
+        }
+        if bl.fade_ms > 10_000 {
+            errors.push("backlight.fade_ms: must be at most 10000".to_string());
+        }
+        if !(50..=20_000).contains(&bl.pwm_hz) {
+            errors.push(format!(
+                "backlight.pwm_hz: {} is not 50 to 20000",
+                bl.pwm_hz
+            ));
+        }
+
+        validate_schedule(&self.schedule, &mut errors);
+
+        let idle = &self.idle;
+        if idle.dim_percent > 100 {
+            errors.push(format!(
+                "idle.dim_percent: {} is more than 100",
+                idle.dim_percent
+            ));
+        }
+
+        let input = &self.input;
+        if input.debounce_ms > 100 {
+            errors.push("input.debounce_ms: must be at most 100".to_string());
+        }
+        for (key, ms) in [
+            ("input.long_press_ms", input.long_press_ms),
+            ("input.repeat_delay_ms", input.repeat_delay_ms),
+            ("input.double_click_ms", input.double_click_ms),
+        ] {
+            if ms <= input.debounce_ms {
+                errors.push(format!("{}: must be more than input.debounce_ms", key));
+            }
+        }
+        if input.repeat_interval_ms > 0 && input.repeat_interval_ms < 50 {
+            errors.push("input.repeat_interval_ms: must be 0 or at least 50".to_string());
+        }
+
+        validate_bindings(&self.keys.bindings, &mut errors);
+
+        if self.http.host.is_empty() {
+            errors.push("http.host: must not be empty".to_string());
+        }
+        if self.http.port == 0 {
+            errors.push("http.port: must not be 0".to_string());
+        }
+
+        for (key, url) in [
+            ("crypto.btc_cmp_url", &self.crypto.btc_cmp_url),
+            ("crypto.btc_ath_url", &self.crypto.btc_ath_url),
+        ] {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                errors.push(format!("{}: \"{}\" is not an http(s) URL", key, url));
+            }
+        }
+        if self.crypto.req_interval_secs < 60 {
+            errors.push("crypto.req_interval_secs: must be at least 60".to_string());
+        }
+
+        if self.usb.serial_num.is_empty() {
+            errors.push("usb.serial_num: must not be empty".to_string());
+        }
+
+        if self.history.sample_interval_secs == 0 {
+            errors.push("history.sample_interval_secs: must be at least 1".to_string());
+        } else if self.history.length_secs < self.history.sample_interval_secs {
+            errors.push(
+                "history.length_secs: must be at least history.sample_interval_secs".to_string(),
+            );
+        }
+
+        let pins = self.pins.named();
+        for (i, (name, pin)) in pins.iter().enumerate() {
+            if *pin > 27 {
+                errors.push(format!("pins.{}: GPIO{} does not exist", name, pin));
+            }
+            if let Some((other, _)) = pins[..i].iter().find(|(_, p)| p == pin) {
+                errors.push(format!(
+                    "pins.{}: GPIO{} is already used by pins.{}",
+                    name, pin, other
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    pub fn panel(&self) -> &'static PanelProfile {
+        panel_profile(&self.screen.panel).unwrap_or(&WAVESHARE_144)
+    }
+
+    /// Configured orientation, falling back to the panel's upright one
+    pub fn orientation(&self) -> LcdOrientation {
+        let orientation = self
+            .screen
+            .orientation
+            .and_then(LcdOrientation::from_degrees)
+            .unwrap_or(self.panel().orientation);
+        if self.screen.flip {
+            orientation.rotated(180)
+        } else {
+            orientation
+        }
+    }
+}
+
+impl Config {
+    // "section.key" -> value, unset options are left out
+    fn flatten(&self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        if let Ok(toml::Value::Table(sections)) = toml::Value::try_from(self) {
+            for (section, table) in sections {
+                if let toml::Value::Table(table) = table {
+                    for (key, value) in table {
+                        map.insert(format!("{}.{}", section, key), value.to_string());
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    /// What changed from `self` to `other`, one "key: old -> new" per entry
+    pub fn diff(&self, other: &Config) -> Vec<String> {
+        let (old, new) = (self.flatten(), other.flatten());
+        let unset = String::from("(unset)");
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| {
+                format!(
+                    "{}: {} -> {}",
+                    key,
+                    old.get(key).unwrap_or(&unset),
+                    new.get(key).unwrap_or(&unset)
+                )
+            })
+            .collect()
+    }
+}
+
+/// Re-read the config file, from the same place as at startup. An
+/// invalid file is rejected and the running config kept. Returns true
+/// if the file was good, changed or not, the layout file may have.
+pub fn config_reload(path: Option<&Path>, shared: &SharedConfig) -> bool {
+    let new = match Config::load_or_default(path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}(): {}, keeping the old config", func_name!(), e);
+            return false;
+        }
+    };
+
+    let mut config = shared.lock().unwrap();
+    let changes = config.diff(&new);
+    if changes.is_empty() {
+        info!("{}(): no changes", func_name!());
+        return true;
+    }
+    for change in &changes {
+        info!("{}(): {}", func_name!(), change);
+    }
+    if config.pins != new.pins {
+        warn!("{}(): pin changes need a restart", func_name!());
+    }
+    if config.backlight.pwm_hz != new.backlight.pwm_hz {
+        warn!(
+            "{}(): backlight.pwm_hz changes need a restart",
+            func_name!()
+        );
+    }
+    *config = new;
+    true
+}
+
+impl PagesConfig {
+    pub fn rotation(&self) -> Option<Duration> {
+        (self.rotate_secs > 0).then(|| Duration::from_secs(self.rotate_secs))
+    }
+}
+
+impl BacklightConfig {
+    pub fn fade(&self) -> Duration {
+        Duration::from_millis(self.fade_ms)
+    }
+}
+
+impl IdleConfig {
+    /// None if it doesn't dim
+    pub fn dim(&self) -> Option<Duration> {
+        (self.dim_secs > 0).then(|| Duration::from_secs(self.dim_secs))
+    }
+
+    /// None if it stays on, from the last key like dim()
+    pub fn off(&self) -> Option<Duration> {
+        (self.off_secs > 0).then(|| Duration::from_secs(self.dim_secs + self.off_secs))
+    }
+}
+
+impl InputConfig {
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+
+    pub fn long_press(&self) -> Duration {
+        Duration::from_millis(self.long_press_ms)
+    }
+
+    pub fn repeat_delay(&self) -> Duration {
+        Duration::from_millis(self.repeat_delay_ms)
+    }
+
+    /// None if keys don't repeat
+    pub fn repeat(&self) -> Option<Duration> {
+        (self.repeat_interval_ms > 0).then(|| Duration::from_millis(self.repeat_interval_ms))
+    }
+
+    pub fn double_click(&self) -> Duration {
+        Duration::from_millis(self.double_click_ms)
+    }
+}
+
+impl HistoryConfig {
+    /// Number of samples kept
+    pub fn capacity(&self) -> usize {
+        (self.length_secs / self.sample_interval_secs.max(1)).max(1) as usize
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.sample_interval_secs.max(1))
+    }
+}
+
+impl PinConfig {
+    pub fn named(&self) -> [(&'static str, UBYTE); 12] {
+        [
+            ("lcd_cs", self.lcd_cs),
+            ("lcd_rst", self.lcd_rst),
+            ("lcd_dc", self.lcd_dc),
+            ("lcd_bl", self.lcd_bl),
+            ("key_up", self.key_up),
+            ("key_down", self.key_down),
+            ("key_left", self.key_left),
+            ("key_right", self.key_right),
+            ("key_press", self.key_press),
+            ("key1", self.key1),
+            ("key2", self.key2),
+            ("key3", self.key3),
+        ]
+    }
+}
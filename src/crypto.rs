@@ -5,6 +5,7 @@
 //! 05-Jun-2025
 //!
 
+use crate::config::*;
 use crate::defs::CryptoResult;
 use crate::defs::*;
 use log::{LevelFilter, debug, error, info, warn};
@@ -99,15 +100,20 @@ impl CryptoResult {
     }
 }
 
+/// Fetch prices every `req_interval_secs`, a reload that changes the
+/// crypto settings or a refresh from the keys cuts the wait short.
 pub async fn crypto_thd(
+    config: SharedConfig,
     s: crossbeam_channel::Sender<CryptoResult>,
+    refresh: crossbeam_channel::Receiver<()>,
     m: Arc<Mutex<bool>>,
     crypto_result: Arc<Mutex<CryptoResult>>,
 ) {
     let mut c_r;
 
     'outer: loop {
-        c_r = get_btc().await;
+        let crypto_config = config.lock().unwrap().crypto.clone();
+        c_r = get_btc(&crypto_config).await;
 
         if c_r.btc_cmp > 0 {
             s.send(c_r.clone()).unwrap();
@@ -124,19 +130,26 @@ pub async fn crypto_thd(
             break 'outer;
         }
         drop(_exit);
-        thread::sleep(Duration::from_secs(HTTP_CRYPTO_REQ_INTERVAL_SECS));
+        let mut waited = 0;
+        while waited < crypto_config.req_interval_secs
+            && config.lock().unwrap().crypto == crypto_config
+            && refresh.try_recv().is_err()
+        {
+            thread::sleep(Duration::from_secs(1));
+            waited += 1;
+        }
     }
     drop(s);
     drop(crypto_result);
 }
 
-pub async fn get_btc() -> CryptoResult {
+pub async fn get_btc(config: &CryptoConfig) -> CryptoResult {
     let mut btc_ath = 0;
     let mut btc_cmp = 0;
 
-    btc_cmp = match reqwest::get(HTTP_BTC_CMP_URL).await {
+    btc_cmp = match reqwest::get(&config.btc_cmp_url).await {
         Ok(resp) => {
-            btc_ath = match reqwest::get(HTTP_BTC_ATH_URL).await {
+            btc_ath = match reqwest::get(&config.btc_ath_url).await {
                 Ok(resp) => resp
                     .text()
                     .await
@@ -1,4 +1,5 @@
-//! All the defs, The first few entries can be customized.
+//! All the defs. The first few entries are the defaults for the config
+//! file, see config.rs and LCD_config.toml.
 //!
 //! defs.rs
 //! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
@@ -7,11 +8,45 @@
 
 pub const SCREEN_UPDATE_INTERVAL_SECS: u64 = 5;
 
+/// Turn to the next page every so many seconds, 0 to only turn them
+/// with the joystick
+pub const PAGES_ROTATE_SECS: u64 = 0;
+
+/// Backlight: level for On (SIGUSR2) and at startup, KEY2 step, the
+/// gamma of the brightness curve, how long changes fade over and the
+/// PWM frequency
+pub const BACKLIGHT_ON_PERCENT: u8 = 60;
+pub const BACKLIGHT_STEP_PERCENT: u8 = 20;
+pub const BACKLIGHT_GAMMA: f32 = 2.2;
+pub const BACKLIGHT_FADE_MS: u64 = 400;
+pub const BACKLIGHT_PWM_HZ: u32 = 500;
+
+/// Idle timeout: seconds without a key before the backlight dims, more
+/// seconds before the panel goes off (0 for never), the dimmed level, and
+/// the CPU temperature that wakes it
+pub const IDLE_DIM_SECS: u64 = 0;
+pub const IDLE_OFF_SECS: u64 = 0;
+pub const IDLE_DIM_PERCENT: u8 = 10;
+pub const IDLE_ALERT_CPU_TEMP: f32 = 80.0;
+
+/// Key timing: bounces closer than this are dropped, held this long is
+/// a long press, repeats start after the delay and come at the interval
+/// (0 for none), a second click this soon after the first is a double
+pub const INPUT_DEBOUNCE_MS: u64 = 20;
+pub const INPUT_LONG_PRESS_MS: u64 = 1000;
+pub const INPUT_REPEAT_DELAY_MS: u64 = 500;
+pub const INPUT_REPEAT_INTERVAL_MS: u64 = 250;
+pub const INPUT_DOUBLE_CLICK_MS: u64 = 300;
+
+/// Stats history for the charts
+pub const HISTORY_SAMPLE_INTERVAL_SECS: u64 = 10;
+pub const HISTORY_LENGTH_SECS: u64 = 60 * 60; // 1 hour
+
 /// HTTP server to show statistics on a remote device (Raspberry
 /// Pi Zero W with Waveshare 1.3" 240x240 display)
 /// See: <https://github.com/GreenHex/Pico-HTTP-Remote-Status-Display>
 pub const HTTP_HOST: &str = "0.0.0.0";
-pub const HTTP_PORT: &str = "8080";
+pub const HTTP_PORT: u16 = 8080;
 
 /// Free crypto prices server
 pub const HTTP_BTC_CMP_URL: &str = "https://cryptoprices.cc/BTC";
@@ -26,19 +61,20 @@ pub const USB_DEV_VENDOR_ID: u16 = 0x2E8A;
 pub const USB_DEV_PRODUCT_ID: u16 = 0x000A;
 pub const USB_DEV_SERIAL_NUM: &str = "E6616407E361442F";
 
+#[allow(clippy::upper_case_acronyms)]
 pub type UBYTE = u8;
+#[allow(clippy::upper_case_acronyms)]
 pub type UWORD = u16;
+#[allow(clippy::upper_case_acronyms)]
 pub type UDOUBLE = u32;
 
-/// This is for the 1.44" LCD screen
-pub const LCD_WIDTH: usize = 128;
-pub const LCD_HEIGHT: usize = 128;
-
-pub const IMG_WIDTH: usize = LCD_WIDTH;
-pub const IMG_HEIGHT: usize = LCD_HEIGHT;
+/// LCD HAT in use, one of the built-in profiles in panel.rs:
+/// "waveshare-1.44", "waveshare-1.44-vendor", "waveshare-1.3", "ili9341"
+pub const LCD_PANEL: &str = "waveshare-1.44";
 
-pub const LCD_X_CORRECTION: u8 = 0;
-pub const LCD_Y_CORRECTION: u8 = 0;
+/// Turn the display upside down, for HATs mounted that way in the
+/// enclosure. KEY3 flips it at runtime.
+pub const LCD_FLIP: bool = false;
 
 pub const LCD_COLOUR_DEPTH: usize = 2; // bytes
 
@@ -0,0 +1,318 @@
+//! Shape primitives on the `Lcd` image buffer: lines, circles, ellipses,
+//! arcs, rounded rectangles, triangles and polygons.
+//!
+//! Coordinates are signed so shapes can hang off the canvas, everything
+//! is clipped to it. Angles are in degrees, 0 at 3 o'clock, increasing
+//! clockwise (the y axis points down).
+//!
+//! draw.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 11-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::lcd::lcd::*;
+
+impl<B: DisplayBus> Lcd<B> {
+    fn plot(&mut self, x: i32, y: i32, colour: UWORD) {
+        if x >= 0 && y >= 0 {
+            self.img_draw_pixel(x as usize, y as usize, colour);
+        }
+    }
+
+    // Horizontal run from x0 to x1 inclusive, clipped
+    fn span(&mut self, x0: i32, x1: i32, y: i32, colour: UWORD) {
+        let (x0, x1) = (x0.min(x1).max(0), x0.max(x1));
+        if y < 0 || x1 < x0 {
+            return;
+        }
+        self.img_draw_rect(x0 as usize, y as usize, (x1 - x0 + 1) as usize, 1, colour);
+    }
+
+    /// Bresenham line, both end points included
+    pub fn img_draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, colour: UWORD) -> &Self {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        loop {
+            self.plot(x, y, colour);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+        self
+    }
+
+    // Midpoint circle, calls `f` with each (dx, dy) of the first octant
+    fn circle_points(r: i32, mut f: impl FnMut(i32, i32)) {
+        let (mut x, mut y) = (r, 0);
+        let mut err = 1 - r;
+        while x >= y {
+            f(x, y);
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    pub fn img_draw_circle(&mut self, cx: i32, cy: i32, r: i32, colour: UWORD) -> &Self {
+        self.img_draw_arc(cx, cy, r, 0, 360, colour)
+    }
+
+    pub fn img_fill_circle(&mut self, cx: i32, cy: i32, r: i32, colour: UWORD) -> &Self {
+        self.img_fill_ellipse(cx, cy, r, r, colour)
+    }
+
+    /// Circle outline from `start` to `end` degrees, clockwise
+    pub fn img_draw_arc(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        r: i32,
+        start: i32,
+        end: i32,
+        colour: UWORD,
+    ) -> &Self {
+        if r < 0 {
+            return self;
+        }
+        let mut points = Vec::new();
+        Self::circle_points(r, |x, y| {
+            for (dx, dy) in [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ] {
+                points.push((dx, dy));
+            }
+        });
+        for (dx, dy) in points {
+            if angle_in_sweep(dx, dy, start, end) {
+                self.plot(cx + dx, cy + dy, colour);
+            }
+        }
+        self
+    }
+
+    /// Ring segment between radius `r_inner` and `r_outer` (both included)
+    /// from `start` to `end` degrees, clockwise. Gauge dials are made of these.
+    #[allow(clippy::too_many_arguments)]
+    pub fn img_fill_arc(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        r_inner: i32,
+        r_outer: i32,
+        start: i32,
+        end: i32,
+        colour: UWORD,
+    ) -> &Self {
+        let inner = (r_inner * r_inner - r_inner).max(0);
+        let outer = r_outer * r_outer + r_outer;
+        for dy in -r_outer..=r_outer {
+            for dx in -r_outer..=r_outer {
+                let d = dx * dx + dy * dy;
+                if d >= inner && d <= outer && angle_in_sweep(dx, dy, start, end) {
+                    self.plot(cx + dx, cy + dy, colour);
+                }
+            }
+        }
+        self
+    }
+
+    /// Midpoint ellipse outline
+    pub fn img_draw_ellipse(&mut self, cx: i32, cy: i32, rx: i32, ry: i32, colour: UWORD) -> &Self {
+        if rx < 0 || ry < 0 {
+            return self;
+        }
+        let quad = |s: &mut Self, x: i32, y: i32| {
+            s.plot(cx + x, cy + y, colour);
+            s.plot(cx - x, cy + y, colour);
+            s.plot(cx + x, cy - y, colour);
+            s.plot(cx - x, cy - y, colour);
+        };
+        let (rx2, ry2) = (rx as i64 * rx as i64, ry as i64 * ry as i64);
+        let (mut x, mut y) = (0i64, ry as i64);
+
+        // Region 1, slope above -1
+        let mut d = ry2 - rx2 * ry as i64 + rx2 / 4;
+        while ry2 * x < rx2 * y {
+            quad(self, x as i32, y as i32);
+            x += 1;
+            if d < 0 {
+                d += ry2 * (2 * x + 1);
+            } else {
+                y -= 1;
+                d += ry2 * (2 * x + 1) - 2 * rx2 * y;
+            }
+        }
+
+        // Region 2
+        let mut d = ry2 * (2 * x + 1) * (2 * x + 1) / 4 + rx2 * (y - 1) * (y - 1) - rx2 * ry2;
+        while y >= 0 {
+            quad(self, x as i32, y as i32);
+            y -= 1;
+            if d > 0 {
+                d += rx2 * (1 - 2 * y);
+            } else {
+                x += 1;
+                d += 2 * ry2 * x + rx2 * (1 - 2 * y);
+            }
+        }
+        self
+    }
+
+    pub fn img_fill_ellipse(&mut self, cx: i32, cy: i32, rx: i32, ry: i32, colour: UWORD) -> &Self {
+        if rx < 0 || ry < 0 {
+            return self;
+        }
+        for dy in -ry..=ry {
+            let dx = if ry == 0 {
+                rx
+            } else {
+                let t = 1.0 - (dy * dy) as f32 / ((ry as f32 + 0.5) * (ry as f32 + 0.5));
+                ((rx as f32 + 0.5) * t.max(0.0).sqrt()) as i32
+            };
+            self.span(cx - dx, cx + dx, cy + dy, colour);
+        }
+        self
+    }
+
+    pub fn img_draw_round_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        r: i32,
+        colour: UWORD,
+    ) -> &Self {
+        if w <= 0 || h <= 0 {
+            return self;
+        }
+        let r = r.clamp(0, w.min(h) / 2);
+        let (x1, y1) = (x + w - 1, y + h - 1);
+        self.img_draw_line(x + r, y, x1 - r, y, colour);
+        self.img_draw_line(x + r, y1, x1 - r, y1, colour);
+        self.img_draw_line(x, y + r, x, y1 - r, colour);
+        self.img_draw_line(x1, y + r, x1, y1 - r, colour);
+        self.img_draw_arc(x1 - r, y + r, r, 270, 360, colour);
+        self.img_draw_arc(x1 - r, y1 - r, r, 0, 90, colour);
+        self.img_draw_arc(x + r, y1 - r, r, 90, 180, colour);
+        self.img_draw_arc(x + r, y + r, r, 180, 270, colour)
+    }
+
+    pub fn img_fill_round_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        r: i32,
+        colour: UWORD,
+    ) -> &Self {
+        if w <= 0 || h <= 0 {
+            return self;
+        }
+        let r = r.clamp(0, w.min(h) / 2);
+        for j in 0..h {
+            // Distance into the corner, counted from the nearer edge
+            let dy = (r - j).max(j - (h - 1 - r)).max(0);
+            let inset = if dy == 0 {
+                0
+            } else {
+                r - (((r * r + r - dy * dy).max(0)) as f32).sqrt() as i32
+            };
+            self.span(x + inset, x + w - 1 - inset, y + j, colour);
+        }
+        self
+    }
+
+    pub fn img_draw_triangle(&mut self, points: [(i32, i32); 3], colour: UWORD) -> &Self {
+        self.img_draw_polygon(&points, colour)
+    }
+
+    pub fn img_fill_triangle(&mut self, points: [(i32, i32); 3], colour: UWORD) -> &Self {
+        self.img_fill_polygon(&points, colour)
+    }
+
+    /// Closed outline through all the points
+    pub fn img_draw_polygon(&mut self, points: &[(i32, i32)], colour: UWORD) -> &Self {
+        for (i, &(x0, y0)) in points.iter().enumerate() {
+            let (x1, y1) = points[(i + 1) % points.len()];
+            self.img_draw_line(x0, y0, x1, y1, colour);
+        }
+        self
+    }
+
+    /// Scanline fill, even-odd rule, sampled at pixel centres. Works for
+    /// concave and self-intersecting polygons too.
+    pub fn img_fill_polygon(&mut self, points: &[(i32, i32)], colour: UWORD) -> &Self {
+        if points.len() < 3 {
+            return self.img_draw_polygon(points, colour);
+        }
+        let (img_w, img_h) = self.img_size();
+        let y_min = points.iter().map(|p| p.1).min().unwrap().max(0);
+        let y_max = points
+            .iter()
+            .map(|p| p.1)
+            .max()
+            .unwrap()
+            .min(img_h as i32 - 1);
+        let mut xs: Vec<f32> = Vec::new();
+
+        for y in y_min..=y_max {
+            let yc = y as f32 + 0.5;
+            xs.clear();
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+                let (fy0, fy1) = (y0 as f32 + 0.5, y1 as f32 + 0.5);
+                if (fy0 <= yc && yc < fy1) || (fy1 <= yc && yc < fy0) {
+                    let t = (yc - fy0) / (fy1 - fy0);
+                    xs.push(x0 as f32 + t * (x1 - x0) as f32);
+                }
+            }
+            xs.sort_by(|a, b| a.total_cmp(b));
+            for pair in xs.chunks_exact(2) {
+                // Pixels whose centres fall in [left, right)
+                let x0 = (pair[0] - 0.5).ceil() as i32;
+                let x1 = (pair[1] - 0.5).ceil() as i32 - 1;
+                if x0 <= x1 {
+                    self.span(x0, x1.min(img_w as i32 - 1), y, colour);
+                }
+            }
+        }
+        self
+    }
+}
+
+// True if the direction (dx, dy) lies within the clockwise sweep from
+// `start` to `end` degrees. A sweep of 360 or more is a full circle.
+fn angle_in_sweep(dx: i32, dy: i32, start: i32, end: i32) -> bool {
+    if end - start >= 360 {
+        return true;
+    }
+    let sweep = (end - start).rem_euclid(360);
+    let angle = (dy as f32).atan2(dx as f32).to_degrees().round() as i32;
+    (angle - start).rem_euclid(360) <= sweep
+}
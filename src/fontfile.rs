@@ -0,0 +1,360 @@
+//! Bitmap fonts loaded at runtime: BDF (X11) and PSF version 1 and 2
+//! (Linux console) files, for other sizes and languages than the
+//! built-in fonts. PCF fonts can be converted to BDF with pcf2bdf.
+//!
+//! fontfile.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 16-Jul-2025
+//!
+
+use crate::defs::*;
+use crate::text::*;
+use log::{LevelFilter, debug, error, info, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_MODEHASTAB: u8 = 0x02;
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+
+#[derive(Debug)]
+pub enum FontError {
+    Read(PathBuf, std::io::Error),
+    Parse(String),
+    Unsupported, // not a BDF or PSF file
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Read(path, e) => write!(f, "can't read {}: {}", path.display(), e),
+            FontError::Parse(e) => write!(f, "can't parse font: {}", e),
+            FontError::Unsupported => write!(f, "not a BDF or PSF font"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BitmapGlyph {
+    width: usize,
+    height: usize,
+    x_offset: i32,
+    y_offset: i32, // from the top of the line
+    advance: usize,
+    stride: usize,
+    data: Vec<u8>,
+}
+
+/// 1-bit font with any set of characters
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapFont {
+    name: String,
+    height: usize,
+    glyphs: HashMap<char, BitmapGlyph>,
+}
+
+impl Font for BitmapFont {
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.glyphs.get(&c).map(|g| Glyph {
+            width: g.width,
+            height: g.height,
+            x_offset: g.x_offset,
+            y_offset: g.y_offset,
+            advance: g.advance,
+            bitmap: GlyphBitmap::Mono {
+                data: &g.data,
+                stride: g.stride,
+            },
+        })
+    }
+}
+
+impl BitmapFont {
+    /// BDF or PSF, the format is taken from the file's header
+    pub fn load(path: &Path) -> Result<Self, FontError> {
+        let bytes = std::fs::read(path).map_err(|e| FontError::Read(path.to_path_buf(), e))?;
+        let font = Self::from_bytes(&bytes)?;
+        debug!(
+            "{}(): {} \"{}\", {} glyphs, {} px high",
+            func_name!(),
+            path.display(),
+            font.name,
+            font.glyphs.len(),
+            font.height
+        );
+        Ok(font)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FontError> {
+        if bytes.starts_with(&PSF2_MAGIC) {
+            Self::from_psf2(bytes)
+        } else if bytes.starts_with(&PSF1_MAGIC) {
+            Self::from_psf1(bytes)
+        } else if bytes.starts_with(b"STARTFONT") {
+            let text = std::str::from_utf8(bytes).map_err(|e| FontError::Parse(e.to_string()))?;
+            Self::from_bdf(text)
+        } else {
+            Err(FontError::Unsupported)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    pub fn from_bdf(text: &str) -> Result<Self, FontError> {
+        let parse_err =
+            |line: usize, what: &str| FontError::Parse(format!("line {}: {}", line, what));
+        let mut name = String::new();
+        let mut bbox = (0usize, 0usize, 0i32, 0i32);
+        let (mut ascent, mut descent) = (None, None);
+        let mut glyphs = HashMap::new();
+
+        // Current glyph: encoding, advance, bounding box, bitmap rows
+        let mut encoding: Option<u32> = None;
+        let mut advance = 0;
+        let mut glyph_bbx = (0usize, 0usize, 0i32, 0i32);
+        let mut rows: Option<Vec<Vec<u8>>> = None;
+
+        for (n, line) in text.lines().enumerate() {
+            let n = n + 1;
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let mut num = |what: &str| -> Result<i32, FontError> {
+                words
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .ok_or_else(|| parse_err(n, what))
+            };
+
+            // Hex bitmap rows until ENDCHAR
+            if let Some(rows) = rows.as_mut()
+                && keyword != "ENDCHAR"
+            {
+                let row = keyword
+                    .as_bytes()
+                    .chunks_exact(2)
+                    .map(|p| {
+                        str::from_utf8(p)
+                            .ok()
+                            .and_then(|p| u8::from_str_radix(p, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| parse_err(n, "bad bitmap row"))?;
+                rows.push(row);
+                continue;
+            }
+
+            match keyword {
+                "FONT" => name = words.collect::<Vec<_>>().join(" "),
+                "FONTBOUNDINGBOX" => {
+                    bbox = (
+                        num("FONTBOUNDINGBOX")?.max(0) as usize,
+                        num("FONTBOUNDINGBOX")?.max(0) as usize,
+                        num("FONTBOUNDINGBOX")?,
+                        num("FONTBOUNDINGBOX")?,
+                    )
+                }
+                "FONT_ASCENT" => ascent = Some(num("FONT_ASCENT")?),
+                "FONT_DESCENT" => descent = Some(num("FONT_DESCENT")?),
+                "STARTCHAR" => {
+                    encoding = None;
+                    advance = bbox.0;
+                    glyph_bbx = bbox;
+                }
+                "ENCODING" => encoding = u32::try_from(num("ENCODING")?).ok(),
+                "DWIDTH" => advance = num("DWIDTH")?.max(0) as usize,
+                "BBX" => {
+                    glyph_bbx = (
+                        num("BBX")?.max(0) as usize,
+                        num("BBX")?.max(0) as usize,
+                        num("BBX")?,
+                        num("BBX")?,
+                    )
+                }
+                "BITMAP" => rows = Some(Vec::new()),
+                "ENDCHAR" => {
+                    let rows = rows
+                        .take()
+                        .ok_or_else(|| parse_err(n, "ENDCHAR without BITMAP"))?;
+                    let (w, h, x_off, y_off) = glyph_bbx;
+                    let stride = w.div_ceil(8);
+                    if rows.len() < h || rows.iter().any(|r| r.len() < stride) {
+                        return Err(parse_err(n, "bitmap smaller than BBX"));
+                    }
+                    // Unencoded glyphs (-1) are left out
+                    if let Some(c) = encoding.and_then(char::from_u32) {
+                        let font_ascent = ascent.unwrap_or(bbox.1 as i32 + bbox.3);
+                        glyphs.insert(
+                            c,
+                            BitmapGlyph {
+                                width: w,
+                                height: h,
+                                x_offset: x_off,
+                                y_offset: font_ascent - (y_off + h as i32),
+                                advance,
+                                stride,
+                                data: rows
+                                    .iter()
+                                    .take(h)
+                                    .flat_map(|r| r[..stride].to_vec())
+                                    .collect(),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if glyphs.is_empty() {
+            return Err(FontError::Parse("no glyphs".to_string()));
+        }
+        let height =
+            (ascent.unwrap_or(bbox.1 as i32 + bbox.3) + descent.unwrap_or(-bbox.3)).max(1) as usize;
+        Ok(Self {
+            name,
+            height,
+            glyphs,
+        })
+    }
+
+    pub fn from_psf1(bytes: &[u8]) -> Result<Self, FontError> {
+        let (mode, height) = match bytes {
+            [_, _, mode, height, ..] => (*mode, *height as usize),
+            _ => return Err(FontError::Parse("short PSF1 header".to_string())),
+        };
+        let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+        let table =
+            (mode & PSF1_MODEHASTAB != 0).then(|| &bytes[(4 + count * height).min(bytes.len())..]);
+
+        // Unicode table: u16 code points per glyph, 0xFFFE starts
+        // sequences (ignored), 0xFFFF ends the glyph
+        let unicode = table.map(|table| {
+            let mut map = Vec::new();
+            let mut glyph = 0;
+            let mut in_seq = false;
+            for pair in table.chunks_exact(2) {
+                match u16::from_le_bytes([pair[0], pair[1]]) {
+                    0xFFFF => {
+                        glyph += 1;
+                        in_seq = false;
+                    }
+                    0xFFFE => in_seq = true,
+                    u if !in_seq => {
+                        if let Some(c) = char::from_u32(u as u32) {
+                            map.push((c, glyph));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            map
+        });
+        Self::from_psf_glyphs(bytes, 4, count, 8, height, unicode)
+    }
+
+    pub fn from_psf2(bytes: &[u8]) -> Result<Self, FontError> {
+        let word = |i: usize| {
+            bytes
+                .get(i * 4..i * 4 + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                .ok_or_else(|| FontError::Parse("short PSF2 header".to_string()))
+        };
+        let (header_size, flags, count, glyph_size, height, width) =
+            (word(2)?, word(3)?, word(4)?, word(5)?, word(6)?, word(7)?);
+        if width.div_ceil(8).checked_mul(height) != Some(glyph_size) {
+            return Err(FontError::Parse(
+                "PSF2 glyph size does not match".to_string(),
+            ));
+        }
+        // The header is read from the file, keep the sums in range
+        let end = count
+            .checked_mul(glyph_size)
+            .and_then(|size| size.checked_add(header_size))
+            .filter(|&end| end <= bytes.len())
+            .ok_or_else(|| FontError::Parse("truncated PSF glyph data".to_string()))?;
+
+        // Unicode table: UTF-8 per glyph, 0xFE starts sequences (ignored),
+        // 0xFF ends the glyph
+        let unicode = (flags as u32 & PSF2_HAS_UNICODE_TABLE != 0).then(|| {
+            bytes[end..]
+                .split(|&b| b == 0xFF)
+                .take(count)
+                .enumerate()
+                .flat_map(|(glyph, entry)| {
+                    let singles = entry.split(|&b| b == 0xFE).next().unwrap_or(&[]);
+                    String::from_utf8_lossy(singles)
+                        .chars()
+                        .filter(|&c| c != char::REPLACEMENT_CHARACTER)
+                        .map(|c| (c, glyph))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        });
+        Self::from_psf_glyphs(bytes, header_size, count, width, height, unicode)
+    }
+
+    // Without a Unicode table glyph n is character n
+    fn from_psf_glyphs(
+        bytes: &[u8],
+        offset: usize,
+        count: usize,
+        width: usize,
+        height: usize,
+        unicode: Option<Vec<(char, usize)>>,
+    ) -> Result<Self, FontError> {
+        let stride = width.div_ceil(8);
+        let end = stride
+            .checked_mul(height)
+            .filter(|_| width > 0 && height > 0)
+            .and_then(|size| Some((size, size.checked_mul(count)?.checked_add(offset)?)));
+        let size = match end {
+            Some((size, end)) if end <= bytes.len() => size,
+            _ => return Err(FontError::Parse("truncated PSF glyph data".to_string())),
+        };
+        let glyph = |n: usize| BitmapGlyph {
+            width,
+            height,
+            x_offset: 0,
+            y_offset: 0,
+            advance: width,
+            stride,
+            data: bytes[offset + n * size..offset + (n + 1) * size].to_vec(),
+        };
+        let glyphs = match unicode {
+            Some(map) => map
+                .into_iter()
+                .filter(|&(_, n)| n < count)
+                .map(|(c, n)| (c, glyph(n)))
+                .collect(),
+            None => (0..count)
+                .filter_map(|n| char::from_u32(n as u32).map(|c| (c, glyph(n))))
+                .collect(),
+        };
+        Ok(Self {
+            name: format!("PSF {}x{}", width, height),
+            height,
+            glyphs,
+        })
+    }
+}
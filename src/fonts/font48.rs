@@ -42,6 +42,7 @@ pub const FONT48: FontTable<18432> = FontTable {
     height: 48,
 };
 
+#[allow(clippy::large_const_arrays)]
 pub const FONT48_TABLE: [u8; 18432] = [
     //U+0020( )
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -42,6 +42,7 @@ pub const FONT50: FontTable<19200> = FontTable {
     height: 50,
 };
 
+#[allow(clippy::large_const_arrays)]
 pub const FONT50_TABLE: [u8; 19200] = [
     //U+0020( )
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -0,0 +1,726 @@
+//! Extended glyphs for the built-in fonts. The `FontTable`s only cover
+//! printable ASCII, the rest of Latin-1, the degree, euro and bitcoin
+//! signs, ellipsis, arrows and a replacement box are composed from the
+//! ASCII glyphs and a few drawn marks, in each font's own size and stroke
+//! weight.
+//!
+//! Glyphs are built the first time they are asked for and kept for the
+//! life of the program, a few hundred bytes per character and font.
+//!
+//! glyphs.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 17-Jul-2025
+//!
+
+use crate::defs::*;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Drawn for characters no font has
+pub const REPLACEMENT_CHAR: char = '\u{FFFD}';
+
+// (width, height, char) -> packed glyph, fonts are told apart by size
+type GlyphCache = HashMap<(usize, usize, char), &'static [u8]>;
+static CACHE: LazyLock<Mutex<GlyphCache>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Glyph bitmap for `c`, from the font's own table for ASCII
+pub fn table_glyph<const N: usize>(font: &FontTable<N>, c: char) -> Option<&[u8]> {
+    if (' '..='~').contains(&c) {
+        let size = font.width.div_ceil(8) * font.height;
+        let offset = (c as usize - ' ' as usize) * size;
+        font.table.get(offset..offset + size)
+    } else {
+        extended_glyph(font, c)
+    }
+}
+
+/// Glyph for a non-ASCII character in the `FontTable` format, None if
+/// there is no recipe for it
+pub fn extended_glyph<const N: usize>(font: &FontTable<N>, c: char) -> Option<&'static [u8]> {
+    let key = (font.width, font.height, c);
+    if let Some(data) = CACHE.lock().unwrap().get(&key) {
+        return Some(data);
+    }
+    let data: &'static [u8] = Box::leak(compose(font, c)?.pack().into_boxed_slice());
+    CACHE.lock().unwrap().insert(key, data);
+    Some(data)
+}
+
+/// Everything `extended_glyph()` can make
+pub fn extended_chars() -> impl Iterator<Item = char> {
+    ('\u{A0}'..='\u{FF}')
+        .chain(['\u{2026}', '\u{20AC}', '\u{20BF}', REPLACEMENT_CHAR])
+        .chain('\u{2190}'..='\u{2195}')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mark {
+    Grave,
+    Acute,
+    Circumflex,
+    Tilde,
+    Diaeresis,
+    Ring,
+    Cedilla,
+    Stroke,
+}
+
+// Base letter and mark for the accented Latin-1 letters
+fn decompose(c: char) -> Option<(char, Mark)> {
+    use Mark::*;
+    let (base, mark) = match c {
+        'À' => ('A', Grave),
+        'Á' => ('A', Acute),
+        'Â' => ('A', Circumflex),
+        'Ã' => ('A', Tilde),
+        'Ä' => ('A', Diaeresis),
+        'Å' => ('A', Ring),
+        'Ç' => ('C', Cedilla),
+        'È' => ('E', Grave),
+        'É' => ('E', Acute),
+        'Ê' => ('E', Circumflex),
+        'Ë' => ('E', Diaeresis),
+        'Ì' => ('I', Grave),
+        'Í' => ('I', Acute),
+        'Î' => ('I', Circumflex),
+        'Ï' => ('I', Diaeresis),
+        'Ñ' => ('N', Tilde),
+        'Ò' => ('O', Grave),
+        'Ó' => ('O', Acute),
+        'Ô' => ('O', Circumflex),
+        'Õ' => ('O', Tilde),
+        'Ö' => ('O', Diaeresis),
+        'Ø' => ('O', Stroke),
+        'Ù' => ('U', Grave),
+        'Ú' => ('U', Acute),
+        'Û' => ('U', Circumflex),
+        'Ü' => ('U', Diaeresis),
+        'Ý' => ('Y', Acute),
+        'à' => ('a', Grave),
+        'á' => ('a', Acute),
+        'â' => ('a', Circumflex),
+        'ã' => ('a', Tilde),
+        'ä' => ('a', Diaeresis),
+        'å' => ('a', Ring),
+        'ç' => ('c', Cedilla),
+        'è' => ('e', Grave),
+        'é' => ('e', Acute),
+        'ê' => ('e', Circumflex),
+        'ë' => ('e', Diaeresis),
+        'ì' => ('ı', Grave),
+        'í' => ('ı', Acute),
+        'î' => ('ı', Circumflex),
+        'ï' => ('ı', Diaeresis),
+        'ñ' => ('n', Tilde),
+        'ò' => ('o', Grave),
+        'ó' => ('o', Acute),
+        'ô' => ('o', Circumflex),
+        'õ' => ('o', Tilde),
+        'ö' => ('o', Diaeresis),
+        'ø' => ('o', Stroke),
+        'ù' => ('u', Grave),
+        'ú' => ('u', Acute),
+        'û' => ('u', Circumflex),
+        'ü' => ('u', Diaeresis),
+        'ý' => ('y', Acute),
+        'ÿ' => ('y', Diaeresis),
+        _ => return None,
+    };
+    Some((base, mark))
+}
+
+// 1-bit canvas the size of one glyph
+#[derive(Debug, Clone)]
+struct Bits {
+    w: usize,
+    h: usize,
+    px: Vec<bool>,
+}
+
+impl Bits {
+    fn new(w: usize, h: usize) -> Self {
+        Self {
+            w,
+            h,
+            px: vec![false; w * h],
+        }
+    }
+
+    fn from_table<const N: usize>(font: &FontTable<N>, c: char) -> Self {
+        let mut bits = Self::new(font.width, font.height);
+        let stride = font.width.div_ceil(8);
+        let offset = (c as usize - ' ' as usize) * stride * font.height;
+        for y in 0..font.height {
+            for x in 0..font.width {
+                bits.px[y * font.width + x] =
+                    font.table[offset + y * stride + x / 8] & (0x80 >> (x % 8)) != 0;
+            }
+        }
+        bits
+    }
+
+    fn get(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.w
+            && (y as usize) < self.h
+            && self.px[y as usize * self.w + x as usize]
+    }
+
+    fn put(&mut self, x: i32, y: i32, on: bool) {
+        if x >= 0 && y >= 0 && (x as usize) < self.w && (y as usize) < self.h {
+            self.px[y as usize * self.w + x as usize] = on;
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32) {
+        self.put(x, y, true);
+    }
+
+    // Bounding box of the set pixels, inclusive
+    fn ink(&self) -> Option<(i32, i32, i32, i32)> {
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+        for y in 0..self.h as i32 {
+            for x in 0..self.w as i32 {
+                if self.get(x, y) {
+                    bounds = Some(match bounds {
+                        Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                        None => (x, y, x, y),
+                    });
+                }
+            }
+        }
+        bounds
+    }
+
+    fn rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        for j in y..y + h {
+            for i in x..x + w {
+                self.set(i, j);
+            }
+        }
+    }
+
+    // Bresenham line, `t` pixels thick
+    fn line(&mut self, (x0, y0): (i32, i32), (x1, y1): (i32, i32), t: i32) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        loop {
+            self.rect(x - (t - 1) / 2, y - (t - 1) / 2, t, t);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // Ring centred on (cx, cy), radius r, `t` thick
+    fn ring(&mut self, cx: f32, cy: f32, r: f32, t: i32) {
+        for y in 0..self.h as i32 {
+            for x in 0..self.w as i32 {
+                let d = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+                if d <= r && d > r - t as f32 {
+                    self.set(x, y);
+                }
+            }
+        }
+    }
+
+    // Nearest neighbour copy of `src` rectangle (x, y, w, h) into `dst`
+    // rectangle, or'd with what is there
+    fn blit(&mut self, other: &Bits, src: (i32, i32, i32, i32), dst: (i32, i32, i32, i32)) {
+        let (sx, sy, sw, sh) = src;
+        let (dx, dy, dw, dh) = dst;
+        if sw <= 0 || sh <= 0 || dw <= 0 || dh <= 0 {
+            return;
+        }
+        for j in 0..dh {
+            for i in 0..dw {
+                if other.get(sx + i * sw / dw, sy + j * sh / dh) {
+                    self.set(dx + i, dy + j);
+                }
+            }
+        }
+    }
+
+    fn rotated(&self) -> Self {
+        let mut bits = Self::new(self.w, self.h);
+        for y in 0..self.h as i32 {
+            for x in 0..self.w as i32 {
+                bits.put(self.w as i32 - 1 - x, self.h as i32 - 1 - y, self.get(x, y));
+            }
+        }
+        bits
+    }
+
+    fn pack(&self) -> Vec<u8> {
+        let stride = self.w.div_ceil(8);
+        let mut data = vec![0; stride * self.h];
+        for y in 0..self.h {
+            for x in 0..self.w {
+                if self.px[y * self.w + x] {
+                    data[y * stride + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        data
+    }
+}
+
+// Where things are in the font, taken from its ASCII glyphs
+struct Metrics {
+    cap_top: i32,
+    x_top: i32,
+    baseline: i32, // first row below the letters
+    descent: i32,  // last row of the descenders
+    stroke: i32,
+    mark: i32, // accent height
+}
+
+impl Metrics {
+    fn of<const N: usize>(font: &FontTable<N>) -> Self {
+        let ink = |c| Bits::from_table(font, c).ink().unwrap_or((0, 0, 0, 0));
+        let (_, cap_top, _, cap_bottom) = ink('H');
+        let (_, x_top, _, _) = ink('x');
+        let (_, _, _, descent) = ink('p');
+        let cap = cap_bottom + 1 - cap_top;
+        // Stem of the 'I' halfway down, clear of the serifs
+        let i = Bits::from_table(font, 'I');
+        let mid = (cap_top + cap_bottom) / 2;
+        let stem = (0..font.width as i32).filter(|&x| i.get(x, mid)).count() as i32;
+        Self {
+            cap_top,
+            x_top,
+            baseline: cap_bottom + 1,
+            descent,
+            stroke: stem.clamp(1, 4),
+            mark: (cap / 4).max(2),
+        }
+    }
+}
+
+fn compose<const N: usize>(font: &FontTable<N>, c: char) -> Option<Bits> {
+    let m = Metrics::of(font);
+    let ascii = |c: char| Bits::from_table(font, c);
+    let (w, h) = (font.width as i32, font.height as i32);
+    let cx = w / 2;
+    let t = m.stroke;
+    let mut bits = Bits::new(font.width, font.height);
+
+    if let Some((base, mark)) = decompose(c) {
+        let base = if base == 'ı' {
+            // Dotless i, the dot is anything above the x-height
+            let mut i = ascii('i');
+            for y in 0..m.x_top {
+                for x in 0..w {
+                    i.put(x, y, false);
+                }
+            }
+            i
+        } else {
+            ascii(base)
+        };
+        return Some(accented(base, mark, &m));
+    }
+
+    match c {
+        '\u{A0}' => {}
+        '\u{AD}' => return Some(ascii('-')),
+        '¡' => return Some(shift_to_baseline(ascii('!').rotated(), &m)),
+        '¿' => return Some(shift_to_baseline(ascii('?').rotated(), &m)),
+        '¢' => {
+            bits = ascii('c');
+            bits.line((cx, m.x_top - 1), (cx, m.baseline), t);
+        }
+        '£' => {
+            bits = ascii('L');
+            let mid = (m.cap_top + m.baseline) / 2;
+            bits.line((0, mid), (w * 2 / 3, mid), t);
+        }
+        '¤' => {
+            let r = (m.baseline - m.x_top) as f32 / 2.0;
+            let (cxf, cy) = (w as f32 / 2.0, (m.x_top + m.baseline) as f32 / 2.0);
+            bits.ring(cxf, cy, r, t.min(2));
+            // Short diagonals out from the corners
+            let (d, e) = ((r * 0.7) as i32, (r * 0.7) as i32 + t);
+            let c = (cxf as i32, cy as i32);
+            for (sx, sy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                bits.line(
+                    (c.0 + sx * d, c.1 + sy * d),
+                    (c.0 + sx * e, c.1 + sy * e),
+                    1,
+                );
+            }
+        }
+        '¥' => {
+            bits = ascii('Y');
+            let mid = (m.cap_top + m.baseline) / 2;
+            bits.rect(1, mid + 1, w - 2, 1);
+            bits.rect(1, mid + 3, w - 2, 1);
+        }
+        '¦' => {
+            bits = ascii('|');
+            let mid = (m.cap_top + m.baseline) / 2;
+            for y in mid - t..mid + t {
+                for x in 0..w {
+                    bits.put(x, y, false);
+                }
+            }
+        }
+        '§' => {
+            let s = ascii('S');
+            let (top, bottom) = (m.cap_top, m.descent.max(m.baseline));
+            let half = (bottom - top + 1) / 2;
+            let src = (0, m.cap_top, w, m.baseline - m.cap_top);
+            bits.blit(&s, src, (0, top, w, half));
+            bits.blit(&s, src, (0, top + half, w, half));
+        }
+        '¨' => draw_mark(&mut bits, Mark::Diaeresis, cx, m.cap_top, &m),
+        '¯' => bits.rect(1, m.cap_top, w - 2, t),
+        '´' => draw_mark(&mut bits, Mark::Acute, cx, m.cap_top, &m),
+        '¸' => draw_mark(&mut bits, Mark::Cedilla, cx, m.baseline, &m),
+        '©' | '®' => {
+            let r = (m.baseline - m.cap_top) as f32 / 2.0 + 0.5;
+            let cy = (m.cap_top + m.baseline) as f32 / 2.0;
+            bits.ring(w as f32 / 2.0, cy, r.min(w as f32 / 2.0), t.min(2));
+            let inner = ascii(if c == '©' { 'C' } else { 'R' });
+            let (x0, y0, x1, y1) = inner.ink()?;
+            let size = (r as i32).max(2);
+            bits.blit(
+                &inner,
+                (x0, y0, x1 - x0 + 1, y1 - y0 + 1),
+                (cx - size / 2, cy as i32 - size / 2, size, size),
+            );
+        }
+        'ª' | 'º' => {
+            bits = superscript(&ascii(if c == 'ª' { 'a' } else { 'o' }), &m);
+            let (_, _, _, y1) = bits.ink()?;
+            bits.rect(1, y1 + 2, w - 2, 1);
+        }
+        '«' | '»' => {
+            let arrow = ascii(if c == '«' { '<' } else { '>' });
+            let (x0, y0, x1, y1) = arrow.ink()?;
+            let src = (x0, y0, x1 - x0 + 1, y1 - y0 + 1);
+            let (top, hh) = (m.x_top, m.baseline - m.x_top);
+            bits.blit(&arrow, src, (0, top, w / 2, hh));
+            bits.blit(&arrow, src, (w / 2, top, w / 2, hh));
+        }
+        '¬' => {
+            let y = (m.x_top + m.baseline) / 2 - t;
+            bits.rect(1, y, w - 2, t);
+            bits.rect(w - 1 - t, y, t, (m.baseline - m.x_top) / 2);
+        }
+        '°' => {
+            let d = (w as f32 * 0.5).max(3.0);
+            bits.ring(
+                w as f32 / 2.0,
+                m.cap_top as f32 + d / 2.0,
+                d / 2.0,
+                t.min(2),
+            );
+        }
+        '±' => {
+            let (x0, _, x1, _) = ascii('+').ink()?;
+            let bottom = m.baseline - 2 * t;
+            let mid = (m.cap_top + bottom) / 2;
+            bits.rect(x0, mid - t / 2, x1 - x0 + 1, t);
+            bits.rect(cx - t / 2, m.cap_top + 1, t, bottom - m.cap_top - 1);
+            bits.rect(x0, m.baseline - t, x1 - x0 + 1, t);
+        }
+        '²' | '³' | '¹' => {
+            let digit = match c {
+                '¹' => '1',
+                '²' => '2',
+                _ => '3',
+            };
+            bits = superscript(&ascii(digit), &m);
+        }
+        'µ' => {
+            bits = ascii('u');
+            let (x0, _, _, _) = bits.ink()?;
+            bits.rect(x0, m.x_top, t, m.descent + 1 - m.x_top);
+        }
+        '¶' => {
+            bits = ascii('P');
+            let (x0, y0, x1, _) = bits.ink()?;
+            bits.rect(x0, y0, (x1 - x0) / 2 + 1, (m.baseline - m.cap_top) / 2);
+            bits.rect(
+                x0 + (x1 - x0) / 2,
+                y0,
+                t,
+                m.descent.max(m.baseline) + 1 - y0,
+            );
+        }
+        '·' => {
+            let y = (m.x_top + m.baseline) / 2;
+            bits.rect(cx - t / 2, y - t / 2, t.max(2), t.max(2));
+        }
+        '¼' | '½' | '¾' => {
+            let (num, den) = match c {
+                '¼' => ('1', '4'),
+                '½' => ('1', '2'),
+                _ => ('3', '4'),
+            };
+            fraction(&mut bits, &ascii(num), &ascii(den), &m);
+        }
+        'Æ' | 'æ' => {
+            let (a, e) = if c == 'Æ' {
+                (ascii('A'), ascii('E'))
+            } else {
+                (ascii('a'), ascii('e'))
+            };
+            let (ax0, _, ax1, _) = a.ink()?;
+            let (ex0, _, ex1, _) = e.ink()?;
+            let half = w / 2 + 1;
+            bits.blit(&a, (ax0, 0, ax1 - ax0 + 1, h), (0, 0, half, h));
+            bits.blit(&e, (ex0, 0, ex1 - ex0 + 1, h), (w - half, 0, half, h));
+        }
+        'Ð' => {
+            bits = ascii('D');
+            let (x0, _, _, _) = bits.ink()?;
+            let mid = (m.cap_top + m.baseline) / 2;
+            bits.rect((x0 - t).max(0), mid - t / 2, 3 * t, t);
+        }
+        'ð' => {
+            bits = ascii('d');
+            let (_, y0, x1, _) = bits.ink()?;
+            bits.line((x1 - 2 * t, y0 + t), (x1 + t, y0), t);
+        }
+        '×' => {
+            let (y0, y1) = (m.x_top, m.baseline - 1);
+            let s = y1 - y0;
+            bits.line((cx - s / 2, y0), (cx + s / 2, y1), t);
+            bits.line((cx - s / 2, y1), (cx + s / 2, y0), t);
+        }
+        'Þ' | 'þ' => {
+            // P moved down, stem up to the top
+            let p = ascii(if c == 'Þ' { 'P' } else { 'p' });
+            let (x0, y0, x1, y1) = p.ink()?;
+            let drop = if c == 'Þ' {
+                (m.baseline - m.cap_top) / 4
+            } else {
+                0
+            };
+            let bottom = if c == 'Þ' { m.baseline - 1 } else { y1 };
+            bits.blit(
+                &p,
+                (x0, y0, x1 - x0 + 1, (bottom - y0 + 1 - drop).max(1)),
+                (x0, y0 + drop, x1 - x0 + 1, (bottom - y0 + 1 - drop).max(1)),
+            );
+            bits.rect(x0, m.cap_top, t, bottom + 1 - m.cap_top);
+        }
+        'ß' => bits = ascii('B'),
+        '÷' => {
+            let y = (m.x_top + m.baseline) / 2;
+            bits.rect(1, y - t / 2, w - 2, t);
+            let d = t.max(2);
+            bits.rect(cx - d / 2, m.x_top, d, d);
+            bits.rect(cx - d / 2, m.baseline - d, d, d);
+        }
+        '…' => {
+            let d = t.max(1);
+            for x in [w / 6, w / 2, w - 1 - w / 6] {
+                bits.rect(x - d / 2, m.baseline - d, d, d);
+            }
+        }
+        '€' => {
+            bits = ascii('C');
+            let mid = (m.cap_top + m.baseline) / 2;
+            let gap = ((m.baseline - m.cap_top) / 6).max(t + 1);
+            for y in [mid - gap, mid + gap - t + 1] {
+                bits.rect(0, y, w * 2 / 3, t);
+            }
+        }
+        '₿' => {
+            // B with strokes above and below, squeezed if the font has no
+            // room for them
+            let b = ascii('B');
+            let (x0, y0, x1, y1) = b.ink()?;
+            let tick = ((y1 - y0) / 6).max(1);
+            let (top, bottom) = if y0 >= tick && h - 1 - y1 >= tick {
+                (y0 - tick, y1 + tick)
+            } else {
+                (y0, y1)
+            };
+            bits.blit(
+                &b,
+                (x0, y0, x1 - x0 + 1, y1 - y0 + 1),
+                (x0, top + tick, x1 - x0 + 1, bottom - top + 1 - 2 * tick),
+            );
+            let third = (x1 - x0) / 3;
+            for x in [x0 + third, x0 + 2 * third] {
+                bits.rect(x, top, t.min(2), tick);
+                bits.rect(x, bottom + 1 - tick, t.min(2), tick);
+            }
+        }
+        '←'..='↕' => arrow(&mut bits, c, &m),
+        REPLACEMENT_CHAR => {
+            let (top, bottom) = (m.cap_top, m.baseline - 1);
+            bits.line((1, top), (w - 2, top), 1);
+            bits.line((1, bottom), (w - 2, bottom), 1);
+            bits.line((1, top), (1, bottom), 1);
+            bits.line((w - 2, top), (w - 2, bottom), 1);
+        }
+        _ => return None,
+    }
+    Some(bits)
+}
+
+// Mark above the letter, squashing it down if there is no room
+fn accented(mut base: Bits, mark: Mark, m: &Metrics) -> Bits {
+    let Some((x0, y0, x1, y1)) = base.ink() else {
+        return base;
+    };
+    let cx = (x0 + x1 + 1) / 2;
+    match mark {
+        Mark::Cedilla => draw_mark(&mut base, mark, cx, y1 + 1, m),
+        Mark::Stroke => base.line((x0, y1), (x1, y0), m.stroke.min(2)),
+        _ => {
+            let need = m.mark + 1;
+            let top = if y0 < need {
+                let mut squashed = Bits::new(base.w, base.h);
+                squashed.blit(
+                    &base,
+                    (0, y0, base.w as i32, y1 - y0 + 1),
+                    (0, need, base.w as i32, y1 - need + 1),
+                );
+                base = squashed;
+                0
+            } else {
+                y0 - need
+            };
+            draw_mark(&mut base, mark, cx, top, m);
+        }
+    }
+    base
+}
+
+// Mark `m.mark` rows high from row `top`, centred on column `cx`
+fn draw_mark(bits: &mut Bits, mark: Mark, cx: i32, top: i32, m: &Metrics) {
+    let h = m.mark;
+    let t = if h >= 4 { m.stroke.min(2) } else { 1 };
+    let half = (h * 2 / 3).max(2);
+    let bottom = top + h - 1;
+    match mark {
+        Mark::Grave => bits.line((cx - half / 2, top), (cx + half / 2, bottom), t),
+        Mark::Acute => bits.line((cx + half / 2, top), (cx - half / 2, bottom), t),
+        Mark::Circumflex => {
+            bits.line((cx - half, bottom), (cx, top), t);
+            bits.line((cx, top), (cx + half, bottom), t);
+        }
+        Mark::Tilde => {
+            let q = (half / 2).max(1);
+            bits.line((cx - half, bottom), (cx - q, top), t);
+            bits.line((cx - q, top), (cx + q, bottom), t);
+            bits.line((cx + q, bottom), (cx + half, top), t);
+        }
+        Mark::Diaeresis => {
+            let d = m.stroke.clamp(1, 2).min(h);
+            let gap = half.max(d);
+            bits.rect(cx - gap - d / 2, bottom + 1 - d, d, d);
+            bits.rect(cx + gap - d / 2, bottom + 1 - d, d, d);
+        }
+        Mark::Ring => {
+            let r = h as f32 / 2.0 + 0.5;
+            bits.ring(cx as f32, top as f32 + r - 0.5, r, 1);
+        }
+        Mark::Cedilla => {
+            let depth = (h / 2).max(2);
+            bits.line((cx, top), (cx, top + depth - 1), t);
+            bits.line((cx, top + depth - 1), (cx - half / 2 - 1, top + depth), t);
+        }
+        Mark::Stroke => {}
+    }
+}
+
+// Half size, top aligned with the capitals
+fn superscript(glyph: &Bits, m: &Metrics) -> Bits {
+    let mut bits = Bits::new(glyph.w, glyph.h);
+    if let Some((x0, y0, x1, y1)) = glyph.ink() {
+        let (gw, gh) = (x1 - x0 + 1, y1 - y0 + 1);
+        let (sw, sh) = ((gw + 1) / 2 + 1, (gh + 1) / 2 + 1);
+        bits.blit(
+            glyph,
+            (x0, y0, gw, gh),
+            ((glyph.w as i32 - sw) / 2, m.cap_top, sw, sh),
+        );
+    }
+    bits
+}
+
+// Numerator top left, denominator bottom right, and a slash
+fn fraction(bits: &mut Bits, num: &Bits, den: &Bits, m: &Metrics) {
+    let (w, cap) = (bits.w as i32, m.baseline - m.cap_top);
+    let (sw, sh) = ((w / 2).max(2), (cap / 2).max(3));
+    for (glyph, x, y) in [(num, 0, m.cap_top), (den, w - sw, m.baseline - sh)] {
+        if let Some((x0, y0, x1, y1)) = glyph.ink() {
+            bits.blit(glyph, (x0, y0, x1 - x0 + 1, y1 - y0 + 1), (x, y, sw, sh));
+        }
+    }
+    bits.line((w - 1, m.cap_top), (0, m.baseline - 1), m.stroke.min(2));
+}
+
+// Inverted glyphs sit on the baseline like the others
+fn shift_to_baseline(glyph: Bits, m: &Metrics) -> Bits {
+    let Some((_, _, _, y1)) = glyph.ink() else {
+        return glyph;
+    };
+    let mut bits = Bits::new(glyph.w, glyph.h);
+    let dy = m.baseline - 1 - y1;
+    for y in 0..glyph.h as i32 {
+        for x in 0..glyph.w as i32 {
+            if glyph.get(x, y) {
+                bits.set(x, y + dy);
+            }
+        }
+    }
+    bits
+}
+
+// ← ↑ → ↓ ↔ ↕ in the x-height band (horizontal) or cap height (vertical)
+fn arrow(bits: &mut Bits, c: char, m: &Metrics) {
+    let (w, t) = (bits.w as i32, m.stroke.min(2));
+    let cy = (m.x_top + m.baseline) / 2;
+    let (left, right) = (0, w - 1);
+    let (top, bottom) = (m.cap_top, m.baseline - 1);
+    let cx = w / 2;
+    let head = ((w - 1) / 3).min((bottom - top) / 3).max(2);
+    let horizontal = |bits: &mut Bits, tip: i32, dir: i32| {
+        bits.line((tip, cy), (tip - dir * head, cy - head), t);
+        bits.line((tip, cy), (tip - dir * head, cy + head), t);
+    };
+    let vertical = |bits: &mut Bits, tip: i32, dir: i32| {
+        bits.line((cx, tip), (cx - head, tip - dir * head), t);
+        bits.line((cx, tip), (cx + head, tip - dir * head), t);
+    };
+    match c {
+        '←' | '→' | '↔' => {
+            bits.line((left, cy), (right, cy), t);
+            if c != '→' {
+                horizontal(bits, left, -1);
+            }
+            if c != '←' {
+                horizontal(bits, right, 1);
+            }
+        }
+        _ => {
+            bits.line((cx, top), (cx, bottom), t);
+            if c != '↓' {
+                vertical(bits, top, -1);
+            }
+            if c != '↑' {
+                vertical(bits, bottom, 1);
+            }
+        }
+    }
+}
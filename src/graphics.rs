@@ -0,0 +1,112 @@
+//! embedded-graphics support: `Lcd`'s image buffer is a `DrawTarget`, so
+//! the ecosystem's shapes, text, images and mono fonts draw straight onto
+//! it. `FontTable` fonts are available as `MonoFont`s.
+//!
+//! graphics.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 12-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::fonts::font8::*;
+use crate::fonts::font12::*;
+use crate::fonts::font16::*;
+use crate::fonts::font20::*;
+use crate::fonts::font24::*;
+use crate::fonts::font48::*;
+use crate::fonts::font50::*;
+use crate::lcd::lcd::*;
+use core::convert::Infallible;
+use embedded_graphics::image::ImageRaw;
+use embedded_graphics::mono_font::{DecorationDimensions, MonoFont};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::pixelcolor::raw::{RawData, RawU16};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+impl<B: DisplayBus> OriginDimensions for Lcd<B> {
+    fn size(&self) -> Size {
+        let (w, h) = self.img_size();
+        Size::new(w as u32, h as u32)
+    }
+}
+
+/// Drawing only touches the image buffer, `flush()` sends it as usual
+impl<B: DisplayBus> DrawTarget for Lcd<B> {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, colour) in pixels {
+            if point.x >= 0 && point.y >= 0 {
+                self.img_draw_pixel(point.x as usize, point.y as usize, to_u16(colour));
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, colour: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if let Some(bottom_right) = area.bottom_right() {
+            self.img_draw_rect(
+                area.top_left.x as usize,
+                area.top_left.y as usize,
+                (bottom_right.x - area.top_left.x + 1) as usize,
+                (bottom_right.y - area.top_left.y + 1) as usize,
+                to_u16(colour),
+            );
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, colour: Self::Color) -> Result<(), Self::Error> {
+        self.img_clear(to_u16(colour));
+        Ok(())
+    }
+}
+
+/// Colour constants above as embedded-graphics colours
+pub fn to_rgb565(colour: UWORD) -> Rgb565 {
+    Rgb565::from(RawU16::new(colour))
+}
+
+pub fn to_u16(colour: Rgb565) -> UWORD {
+    RawU16::from(colour).into_inner()
+}
+
+// FontTable glyphs start at ' ', anything outside ASCII shows as '?'
+fn ascii_glyph_index(c: char) -> usize {
+    if (' '..='~').contains(&c) {
+        c as usize - ' ' as usize
+    } else {
+        '?' as usize - ' ' as usize
+    }
+}
+
+/// A `FontTable` as a `MonoFont`. The table already is a one glyph wide
+/// image strip with byte-padded rows, which is the layout `ImageRaw` wants.
+pub const fn mono_font<const N: usize>(font: &'static FontTable<N>) -> MonoFont<'static> {
+    let (width, height) = (font.width as u32, font.height as u32);
+    let baseline = height * 4 / 5;
+    MonoFont {
+        image: ImageRaw::new(&font.table, width),
+        character_size: Size::new(width, height),
+        character_spacing: 0,
+        baseline,
+        strikethrough: DecorationDimensions::new(height / 2, 1),
+        underline: DecorationDimensions::new(baseline + 1, 1),
+        glyph_mapping: &ascii_glyph_index,
+    }
+}
+
+pub const MONO_FONT8: MonoFont<'static> = mono_font(&FONT8);
+pub const MONO_FONT12: MonoFont<'static> = mono_font(&FONT12);
+pub const MONO_FONT16: MonoFont<'static> = mono_font(&FONT16);
+pub const MONO_FONT20: MonoFont<'static> = mono_font(&FONT20);
+pub const MONO_FONT24: MonoFont<'static> = mono_font(&FONT24);
+pub const MONO_FONT48: MonoFont<'static> = mono_font(&FONT48);
+pub const MONO_FONT50: MonoFont<'static> = mono_font(&FONT50);
@@ -0,0 +1,250 @@
+//! Stats history: load, CPU temperature, memory and network throughput
+//! sampled at a fixed rate into a ring buffer, for the charts.
+//!
+//! history.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 13-Jul-2025
+//!
+
+use crate::config::*;
+use crate::defs::*;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use systemstat::{Platform, System};
+
+/// One reading of everything, `None` where the value could not be read
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sample {
+    pub load: Option<f32>,     // 1 min load average
+    pub cpu_temp: Option<f32>, // deg C
+    pub mem_used: Option<f32>, // percent
+    pub net_rx: Option<f32>,   // bytes/s, all interfaces but loopback
+    pub net_tx: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Load,
+    CpuTemp,
+    MemUsed,
+    NetRx,
+    NetTx,
+}
+
+impl Metric {
+    pub fn value(self, sample: &Sample) -> Option<f32> {
+        match self {
+            Metric::Load => sample.load,
+            Metric::CpuTemp => sample.cpu_temp,
+            Metric::MemUsed => sample.mem_used,
+            Metric::NetRx => sample.net_rx,
+            Metric::NetTx => sample.net_tx,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::Load => "Load",
+            Metric::CpuTemp => "CPU Temp",
+            Metric::MemUsed => "Memory",
+            Metric::NetRx => "Net In",
+            Metric::NetTx => "Net Out",
+        }
+    }
+
+    /// Short value for labels, e.g. "48.3°C" or "1.2k"
+    pub fn format(self, value: f32) -> String {
+        match self {
+            Metric::Load => format!("{:.2}", value),
+            Metric::CpuTemp => format!("{:.1}°C", value),
+            Metric::MemUsed => format!("{:.0}%", value),
+            Metric::NetRx | Metric::NetTx => format_rate(value),
+        }
+    }
+}
+
+fn format_rate(bytes_per_sec: f32) -> String {
+    if bytes_per_sec >= 1_000_000.0 {
+        format!("{:.1}M", bytes_per_sec / 1_000_000.0)
+    } else if bytes_per_sec >= 1_000.0 {
+        format!("{:.1}k", bytes_per_sec / 1_000.0)
+    } else {
+        format!("{:.0}", bytes_per_sec)
+    }
+}
+
+/// Ring buffer of samples, oldest first
+pub struct History {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+    interval: Duration,
+}
+
+impl History {
+    pub fn new(capacity: usize, interval: Duration) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            interval,
+        }
+    }
+
+    /// Sized to hold `length_secs` worth of samples
+    pub fn from_config(config: &HistoryConfig) -> Self {
+        Self::new(config.capacity(), config.interval())
+    }
+
+    /// Keeps the newest samples if it shrinks
+    pub fn resize(&mut self, capacity: usize, interval: Duration) {
+        self.capacity = capacity.max(1);
+        self.interval = interval;
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Time covered by a full buffer
+    pub fn span(&self) -> Duration {
+        self.interval * self.capacity as u32
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    /// One metric over time, oldest first
+    pub fn series(&self, metric: Metric) -> Vec<Option<f32>> {
+        self.samples.iter().map(|s| metric.value(s)).collect()
+    }
+}
+
+/// Reads the stats, keeping the network counters between calls to turn
+/// them into rates.
+pub struct Sampler {
+    sys: System,
+    last_net: Option<(Instant, u64, u64)>,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self {
+            sys: System::new(),
+            last_net: None,
+        }
+    }
+
+    pub fn sample(&mut self) -> Sample {
+        let mem_used = self.sys.memory().ok().and_then(|mem| {
+            let total = mem.total.as_u64();
+            (total > 0)
+                .then(|| (total - mem.free.as_u64().min(total)) as f32 * 100.0 / total as f32)
+        });
+
+        let (net_rx, net_tx) = match self.net_bytes() {
+            Some((rx, tx)) => {
+                let now = Instant::now();
+                let rates = self.last_net.and_then(|(then, last_rx, last_tx)| {
+                    let secs = now.duration_since(then).as_secs_f32();
+                    (secs > 0.0).then(|| {
+                        (
+                            rx.saturating_sub(last_rx) as f32 / secs,
+                            tx.saturating_sub(last_tx) as f32 / secs,
+                        )
+                    })
+                });
+                self.last_net = Some((now, rx, tx));
+                (rates.map(|r| r.0), rates.map(|r| r.1))
+            }
+            None => (None, None),
+        };
+
+        Sample {
+            load: self.sys.load_average().ok().map(|l| l.one),
+            cpu_temp: self.sys.cpu_temp().ok(),
+            mem_used,
+            net_rx,
+            net_tx,
+        }
+    }
+
+    // Total bytes received and sent on all interfaces but loopback
+    fn net_bytes(&self) -> Option<(u64, u64)> {
+        let networks = self.sys.networks().ok()?;
+        let mut total = (0, 0);
+        for name in networks.keys().filter(|name| name.as_str() != "lo") {
+            if let Ok(stats) = self.sys.network_stats(name) {
+                total.0 += stats.rx_bytes.as_u64();
+                total.1 += stats.tx_bytes.as_u64();
+            }
+        }
+        Some(total)
+    }
+}
+
+/// Sampling thread, follows interval and length changes on config reload
+pub fn history_thd(config: SharedConfig, history: Arc<Mutex<History>>, m: Arc<Mutex<bool>>) {
+    let mut sampler = Sampler::new();
+    let mut next = Instant::now();
+
+    loop {
+        let history_config = config.lock().unwrap().history.clone();
+        if Instant::now() >= next {
+            let sample = sampler.sample();
+            debug!("{}(): {:?}", func_name!(), sample);
+
+            let mut h = history.lock().unwrap();
+            if h.capacity() != history_config.capacity()
+                || h.interval() != history_config.interval()
+            {
+                h.resize(history_config.capacity(), history_config.interval());
+            }
+            h.push(sample);
+            drop(h);
+
+            next = Instant::now() + history_config.interval();
+        }
+
+        let _exit = m.lock().unwrap();
+        if *_exit {
+            info!("Exiting {}()", func_name!());
+            break;
+        }
+        drop(_exit);
+        thread::sleep(Duration::from_millis(500));
+    }
+}
@@ -7,40 +7,68 @@
 //! 05-Jun-2025
 //!
 
+use crate::config::*;
 use crate::defs::*;
 use crate::stats::*;
 use log::{LevelFilter, debug, error, info, warn};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
-use tiny_http::{Response, Server};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Request, Response, Server};
 
-pub fn http_server(crypto_result: Arc<Mutex<CryptoResult>>) {
-    let server_str = format!("{}:{}", HTTP_HOST, HTTP_PORT);
+/// Serve until the host or port in the config changes, then rebind
+pub fn http_server(config: SharedConfig, crypto_result: Arc<Mutex<CryptoResult>>) {
+    loop {
+        let http_config = config.lock().unwrap().http.clone();
+        let server_str = format!("{}:{}", http_config.host, http_config.port);
 
-    let server = Server::http(&server_str).expect("Failed to start HTTP server");
-
-    for request in server.incoming_requests() {
-        debug!("{}(): got request", func_name!());
-        debug!(
-            "{}(): got request... method: {:?}, url: {:?}, headers: {:?}",
-            func_name!(),
-            request.method(),
-            request.url(),
-            request.headers()
-        );
-        let header = tiny_http::Header {
-            field: tiny_http::HeaderField::from_str("Content-type")
-                .expect("Failed to set header field"),
-            value: ascii::AsciiString::from_ascii("application/json; charset=utf-8")
-                .expect("Failed to set header value"),
+        let server = match Server::http(&server_str) {
+            Ok(server) => server,
+            Err(e) => {
+                error!(
+                    "{}(): can't listen on {}: {:?}",
+                    func_name!(),
+                    server_str,
+                    e
+                );
+                thread::sleep(Duration::from_secs(10));
+                continue;
+            }
         };
-        request
-            .respond(
-                Response::from_string(get_json_obj(crypto_result.clone()).to_string())
-                    .with_header(header),
-            )
-            .expect("Failed sending request");
+        info!("{}(): listening on {}", func_name!(), server_str);
+
+        while config.lock().unwrap().http == http_config {
+            match server.recv_timeout(Duration::from_secs(1)) {
+                Ok(Some(request)) => respond(request, crypto_result.clone()),
+                Ok(None) => {}
+                Err(e) => error!("{}(): {:?}", func_name!(), e),
+            }
+        }
+        info!("{}(): HTTP config changed, rebinding", func_name!());
     }
-    drop(crypto_result);
+}
+
+fn respond(request: Request, crypto_result: Arc<Mutex<CryptoResult>>) {
+    debug!("{}(): got request", func_name!());
+    debug!(
+        "{}(): got request... method: {:?}, url: {:?}, headers: {:?}",
+        func_name!(),
+        request.method(),
+        request.url(),
+        request.headers()
+    );
+    let header = tiny_http::Header {
+        field: tiny_http::HeaderField::from_str("Content-type")
+            .expect("Failed to set header field"),
+        value: ascii::AsciiString::from_ascii("application/json; charset=utf-8")
+            .expect("Failed to set header value"),
+    };
+    request
+        .respond(
+            Response::from_string(get_json_obj(crypto_result.clone()).to_string())
+                .with_header(header),
+        )
+        .expect("Failed sending request");
 }
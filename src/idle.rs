@@ -0,0 +1,112 @@
+//! Idle timeout: the backlight dims when the keys have not been touched
+//! for a while, then the panel goes off. Any key wakes it, as does an
+//! alert, a stat past its limit, for as long as the alert lasts.
+//!
+//! idle.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 24-Jul-2025
+//!
+
+use crate::config::*;
+use crate::defs::*;
+use crate::history::*;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdleState {
+    Awake,
+    Dimmed, // backlight down to idle.dim_percent
+    Off,    // backlight off, controller asleep
+}
+
+/// Keeps the panel awake while `metric` is above `above`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Alert {
+    pub metric: Metric,
+    pub above: f32,
+}
+
+impl Alert {
+    pub fn firing(&self, sample: &Sample) -> bool {
+        self.metric
+            .value(sample)
+            .is_some_and(|value| value > self.above)
+    }
+}
+
+pub fn default_alerts() -> Vec<Alert> {
+    vec![Alert {
+        metric: Metric::CpuTemp,
+        above: IDLE_ALERT_CPU_TEMP,
+    }]
+}
+
+/// Times out on its own, `activity()` starts it over
+pub struct Idle {
+    config: IdleConfig,
+    last_activity: Instant,
+    state: IdleState,
+}
+
+impl Idle {
+    pub fn new(config: &IdleConfig) -> Self {
+        Self {
+            config: config.clone(),
+            last_activity: Instant::now(),
+            state: IdleState::Awake,
+        }
+    }
+
+    /// New timeouts count from the last activity
+    pub fn reconfigure(&mut self, config: &IdleConfig) {
+        self.config = config.clone();
+    }
+
+    pub fn state(&self) -> IdleState {
+        self.state
+    }
+
+    /// A key or an alert, the new state if it was not awake
+    pub fn activity(&mut self, now: Instant) -> Option<IdleState> {
+        self.last_activity = now;
+        self.set(IdleState::Awake)
+    }
+
+    /// A key seen while awake, which only puts the timeouts off
+    pub fn touch(&mut self, at: Instant) {
+        self.last_activity = self.last_activity.max(at);
+    }
+
+    /// The new state if a timeout passed
+    pub fn tick(&mut self, now: Instant) -> Option<IdleState> {
+        let idle = now.saturating_duration_since(self.last_activity);
+        let state = match (self.config.dim(), self.config.off()) {
+            (_, Some(off)) if idle >= off => IdleState::Off,
+            (Some(dim), _) if idle >= dim => IdleState::Dimmed,
+            _ => IdleState::Awake,
+        };
+        self.set(state)
+    }
+
+    /// Time to the next timeout, None if there is none to come
+    pub fn until_tick(&self, now: Instant) -> Option<Duration> {
+        let next = match self.state {
+            IdleState::Awake => self.config.dim().or(self.config.off()),
+            IdleState::Dimmed => self.config.off(),
+            IdleState::Off => None,
+        }?;
+        Some((self.last_activity + next).saturating_duration_since(now))
+    }
+
+    fn set(&mut self, state: IdleState) -> Option<IdleState> {
+        if state == self.state {
+            return None;
+        }
+        debug!("{}(): {:?} -> {:?}", func_name!(), self.state, state);
+        self.state = state;
+        Some(state)
+    }
+}
@@ -0,0 +1,350 @@
+//! Key input: level changes from the HAT's keys, interrupt driven and
+//! debounced, decoded into presses, releases, long presses, repeats and
+//! double clicks.
+//!
+//! A `Decoder` turns the raw edges into `KeyEvent`s, timed by the
+//! instants on the edges so it runs the same on a `MockInput` as on the
+//! GPIO pins. `input_loop()` ties a source and a decoder together.
+//!
+//! input.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 21-Jul-2025
+//!
+
+use crate::config::*;
+use crate::defs::*;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use log::{LevelFilter, debug, error, info, warn};
+use rppal::gpio::{Gpio, InputPin, Trigger};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Longest wait for an edge, to check for exit
+const POLL_MS: u64 = 100;
+
+/// The five-way joystick and the three keys on the HAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Press,
+    Key1,
+    Key2,
+    Key3,
+}
+
+pub const KEYS: [Key; 8] = [
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Press,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+];
+
+impl Key {
+    pub fn pin(self, pins: &PinConfig) -> UBYTE {
+        match self {
+            Key::Up => pins.key_up,
+            Key::Down => pins.key_down,
+            Key::Left => pins.key_left,
+            Key::Right => pins.key_right,
+            Key::Press => pins.key_press,
+            Key::Key1 => pins.key1,
+            Key::Key2 => pins.key2,
+            Key::Key3 => pins.key3,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEventKind {
+    Press,
+    Release,
+    LongPress,   // once, held for input.long_press_ms
+    Repeat,      // while held, after input.repeat_delay_ms
+    DoubleClick, // with the Press of the second click
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub kind: KeyEventKind,
+}
+
+/// A key going down or up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub key: Key,
+    pub pressed: bool,
+    pub at: Instant,
+}
+
+/// Where edges come from
+pub trait InputSource {
+    /// The next edge, None if there was none within `timeout`
+    fn next_edge(&mut self, timeout: Duration) -> Option<Edge>;
+}
+
+/// The HAT's keys, active low with the pull-ups on. rppal debounces
+/// the interrupts and the callbacks queue the edges. rppal also drops a
+/// real edge inside the debounce time, the release of a quick tap, so
+/// the pins are read again once it has passed.
+pub struct GpioInput {
+    pins: Vec<(Key, InputPin)>, // the interrupts stop when these are dropped
+    r: Receiver<Edge>,
+    debounce: Duration,
+    pressed: [bool; KEYS.len()],               // as last reported
+    changed_at: [Option<Instant>; KEYS.len()], // last edge reported
+}
+
+impl GpioInput {
+    pub fn new(pins: &PinConfig, debounce: Duration) -> Result<Self, rppal::gpio::Error> {
+        let gpio = Gpio::new()?;
+        let (s, r) = unbounded::<Edge>();
+        let mut input_pins = Vec::new();
+        for key in KEYS {
+            let mut pin = gpio.get(key.pin(pins))?.into_input_pullup();
+            let s = s.clone();
+            pin.set_async_interrupt(Trigger::Both, Some(debounce), move |event| {
+                let pressed = event.trigger == Trigger::FallingEdge;
+                let _ = s.send(Edge {
+                    key,
+                    pressed,
+                    at: Instant::now(),
+                });
+            })?;
+            input_pins.push((key, pin));
+        }
+        Ok(Self {
+            pins: input_pins,
+            r,
+            debounce,
+            pressed: [false; KEYS.len()],
+            changed_at: [None; KEYS.len()],
+        })
+    }
+
+    // A key whose level no longer matches what was reported, once it has
+    // had the debounce time to settle
+    fn missed_edge(&self) -> Option<Edge> {
+        let now = Instant::now();
+        self.pins.iter().find_map(|(key, pin)| {
+            let i = key.index();
+            let settled = self.changed_at[i].is_none_or(|at| now >= at + self.debounce);
+            let pressed = pin.is_low();
+            (settled && pressed != self.pressed[i]).then_some(Edge {
+                key: *key,
+                pressed,
+                at: now,
+            })
+        })
+    }
+}
+
+impl InputSource for GpioInput {
+    fn next_edge(&mut self, timeout: Duration) -> Option<Edge> {
+        let edge = self.r.recv_timeout(timeout).ok().or_else(|| {
+            self.missed_edge()
+                .inspect(|edge| debug!("{}(): missed {:?}", func_name!(), edge))
+        })?;
+        self.pressed[edge.key.index()] = edge.pressed;
+        self.changed_at[edge.key.index()] = Some(edge.at);
+        Some(edge)
+    }
+}
+
+/// Keys pressed from code instead of on the HAT, for tests and the
+/// simulator. `MockKeys` is the other end.
+pub struct MockInput {
+    r: Receiver<Edge>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockKeys {
+    s: Sender<Edge>,
+}
+
+pub fn mock_input() -> (MockKeys, MockInput) {
+    let (s, r) = unbounded::<Edge>();
+    (MockKeys { s }, MockInput { r })
+}
+
+impl MockKeys {
+    pub fn press(&self, key: Key) {
+        self.edge(key, true);
+    }
+
+    pub fn release(&self, key: Key) {
+        self.edge(key, false);
+    }
+
+    fn edge(&self, key: Key, pressed: bool) {
+        let _ = self.s.send(Edge {
+            key,
+            pressed,
+            at: Instant::now(),
+        });
+    }
+}
+
+impl InputSource for MockInput {
+    fn next_edge(&mut self, timeout: Duration) -> Option<Edge> {
+        self.r.recv_timeout(timeout).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct KeyState {
+    down_at: Option<Instant>,
+    long_sent: bool,
+    next_repeat: Option<Instant>,
+    double: bool,                // this press was the second click
+    last_click: Option<Instant>, // release of the last short press
+}
+
+/// Turns edges into events. Debouncing is up to the source, edges that
+/// don't change the key's state are dropped.
+pub struct Decoder {
+    config: InputConfig,
+    keys: [KeyState; KEYS.len()],
+}
+
+impl Decoder {
+    pub fn new(config: InputConfig) -> Self {
+        Self {
+            config,
+            keys: [KeyState::default(); KEYS.len()],
+        }
+    }
+
+    pub fn set_config(&mut self, config: InputConfig) {
+        self.config = config;
+    }
+
+    /// Press or Release for the edge, with DoubleClick if it is the
+    /// second of two quick clicks
+    pub fn edge(&mut self, edge: Edge) -> Vec<KeyEvent> {
+        let config = &self.config;
+        let state = &mut self.keys[edge.key.index()];
+        if edge.pressed == state.down_at.is_some() {
+            return Vec::new();
+        }
+
+        let event = |kind| KeyEvent {
+            key: edge.key,
+            kind,
+        };
+        if edge.pressed {
+            state.down_at = Some(edge.at);
+            state.long_sent = false;
+            state.next_repeat = config.repeat().map(|_| edge.at + config.repeat_delay());
+            state.double = state.last_click.take().is_some_and(|then| {
+                edge.at.saturating_duration_since(then) <= config.double_click()
+            });
+            if state.double {
+                vec![event(KeyEventKind::Press), event(KeyEventKind::DoubleClick)]
+            } else {
+                vec![event(KeyEventKind::Press)]
+            }
+        } else {
+            // Only short, single clicks can start a double click
+            let short = !state.long_sent && !state.double;
+            state.last_click = short.then_some(edge.at);
+            state.down_at = None;
+            state.next_repeat = None;
+            vec![event(KeyEventKind::Release)]
+        }
+    }
+
+    /// LongPress and Repeat events due by `now`
+    pub fn tick(&mut self, now: Instant) -> Vec<KeyEvent> {
+        let mut events = Vec::new();
+        for (key, state) in KEYS.iter().zip(self.keys.iter_mut()) {
+            let Some(down_at) = state.down_at else {
+                continue;
+            };
+            if !state.long_sent && now >= down_at + self.config.long_press() {
+                state.long_sent = true;
+                events.push(KeyEvent {
+                    key: *key,
+                    kind: KeyEventKind::LongPress,
+                });
+            }
+            if let (Some(next), Some(interval)) = (state.next_repeat, self.config.repeat())
+                && now >= next
+            {
+                // One at a time, a late tick doesn't make a burst
+                state.next_repeat = Some(now + interval);
+                events.push(KeyEvent {
+                    key: *key,
+                    kind: KeyEventKind::Repeat,
+                });
+            }
+        }
+        events
+    }
+
+    /// When tick() next has something to do, None with no keys down
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.keys
+            .iter()
+            .filter_map(|state| {
+                let down_at = state.down_at?;
+                let long = (!state.long_sent).then(|| down_at + self.config.long_press());
+                match (long, state.next_repeat) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                }
+            })
+            .min()
+    }
+}
+
+/// Decode `source` until told to exit, `handle` gets every event. Timing
+/// changes on config reload are picked up as it goes.
+pub fn input_loop(
+    mut source: impl InputSource,
+    config: SharedConfig,
+    mut handle: impl FnMut(KeyEvent),
+    m: Arc<Mutex<bool>>,
+) {
+    let mut decoder = Decoder::new(config.lock().unwrap().input.clone());
+
+    loop {
+        decoder.set_config(config.lock().unwrap().input.clone());
+
+        let mut timeout = Duration::from_millis(POLL_MS);
+        if let Some(deadline) = decoder.next_deadline() {
+            timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        let mut events = match source.next_edge(timeout) {
+            Some(edge) => decoder.edge(edge),
+            None => Vec::new(),
+        };
+        events.extend(decoder.tick(Instant::now()));
+        for event in events {
+            debug!("{}(): {:?}", func_name!(), event);
+            handle(event);
+        }
+
+        let _exit = m.lock().unwrap();
+        if *_exit {
+            info!("Exiting {}()", func_name!());
+            break;
+        }
+        drop(_exit);
+    }
+}
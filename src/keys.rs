@@ -1,47 +1,116 @@
-//! Check display keys to change brightness or switch off or switch on the display
+//! Act on the display keys as bound in the config, and on signals
 //!
 //! keys.rs
 //! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
 //! 01-Jun-2025
 //!
 
+use crate::bindings::*;
+use crate::config::*;
 use crate::defs::*;
+use crate::idle::IdleState;
+use crate::input::*;
+use crate::lcd::lcd::*;
+use crate::pages::{Overlay, PageCmd};
 use crate::pwm::*;
 use crossbeam_channel::*;
 use log::{LevelFilter, debug, error, info, warn};
-use rppal::gpio::Gpio;
 use signal_hook::consts::*;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-/// Keys polling thread
-pub fn keys_check(s: crossbeam_channel::Sender<BlMode>, m: Arc<Mutex<bool>>) {
-    let pin1 = Gpio::new().unwrap().get(KEY1).unwrap().into_input_pullup();
-    let pin2 = Gpio::new().unwrap().get(KEY2).unwrap().into_input_pullup();
-
-    loop {
-        if pin1.is_low() {
-            s.send(BlMode::Toggle).unwrap();
-        } else if pin2.is_low() {
-            s.send(BlMode::Step).unwrap();
-        }
-        let _exit = m.lock().unwrap();
-        if *_exit {
-            info!("Exiting {}()", func_name!());
-            break;
+/// Keys thread, each key event does what it is bound to in [keys]. Every
+/// event goes in `last_key` to put the idle timeout off, one while the
+/// panel is not awake also wakes it, and a key pressed while it is off
+/// does only that.
+pub fn keys_check(
+    config: SharedConfig,
+    s: crossbeam_channel::Sender<BlMode>,
+    d: crossbeam_channel::Sender<DisplayCmd>,
+    refresh: crossbeam_channel::Sender<()>,
+    idle_state: Arc<Mutex<IdleState>>,
+    last_key: Arc<Mutex<Instant>>,
+    m: Arc<Mutex<bool>>,
+) {
+    let (pins, debounce) = {
+        let config = config.lock().unwrap();
+        (config.pins.clone(), config.input.debounce())
+    };
+    let source = match GpioInput::new(&pins, debounce) {
+        Ok(source) => source,
+        Err(e) => {
+            error!("{}(): {:?}", func_name!(), e);
+            return;
         }
-        drop(_exit);
-        thread::sleep(Duration::from_millis(500));
+    };
+    let bindings = config.clone();
+    let mut waking: Option<Key> = None; // until its release
+    input_loop(
+        source,
+        config,
+        |event| {
+            *last_key.lock().unwrap() = Instant::now();
+            if waking == Some(event.key) {
+                if event.kind == KeyEventKind::Release {
+                    waking = None;
+                }
+                return;
+            }
+            let state = *idle_state.lock().unwrap();
+            if event.kind == KeyEventKind::Press && state == IdleState::Off {
+                waking = Some(event.key);
+            }
+            if state != IdleState::Awake {
+                d.send(DisplayCmd::Wake).unwrap();
+            }
+            if waking.is_some() {
+                return;
+            }
+
+            let bindings = bindings.lock().unwrap().keys.bindings.clone();
+            for action in bound_actions(&bindings, event) {
+                key_action(action, &s, &d, &refresh);
+            }
+        },
+        m,
+    );
+}
+
+fn key_action(action: &Action, s: &Sender<BlMode>, d: &Sender<DisplayCmd>, refresh: &Sender<()>) {
+    debug!("{}(): {:?}", func_name!(), action);
+    let page = |cmd| d.send(DisplayCmd::Page(cmd)).unwrap();
+    let overlay = |overlay| d.send(DisplayCmd::Overlay(overlay)).unwrap();
+    match action {
+        Action::BacklightOn => s.send(BlMode::On).unwrap(),
+        Action::BacklightOff => s.send(BlMode::Off).unwrap(),
+        Action::BacklightToggle => s.send(BlMode::Toggle).unwrap(),
+        Action::BacklightStep => s.send(BlMode::Step).unwrap(),
+        Action::NextPage => page(PageCmd::Next),
+        Action::PrevPage => page(PageCmd::Prev),
+        Action::ScrollUp => page(PageCmd::Up),
+        Action::ScrollDown => page(PageCmd::Down),
+        Action::Select => page(PageCmd::Select),
+        Action::Flip => d.send(DisplayCmd::Flip).unwrap(),
+        Action::CryptoRefresh => refresh.send(()).unwrap(),
+        Action::Reboot => overlay(Overlay::Confirm(Power::Reboot)),
+        Action::Shutdown => overlay(Overlay::Confirm(Power::Shutdown)),
+        Action::IpQr => overlay(Overlay::IpQr),
+        Action::Command(command) => run_command(command),
     }
 }
 
-/// USRSIG1 and USRSIG2 are used to switch on or switch off the display
-/// using crontab. See LCD_crontab for details.
+/// USRSIG1 and USRSIG2 switch the backlight off and on, until the next
+/// transition in the [schedule], USRSIG2 also wakes the panel from idle.
+/// SIGHUP reloads the config.
 pub fn handle_usrsigs(
     s: Sender<BlMode>,
+    d: Sender<DisplayCmd>,
     sigusr_signals: &mut signal_hook::iterator::SignalsInfo,
+    config_path: Option<PathBuf>,
+    config: SharedConfig,
     m: Arc<Mutex<bool>>,
 ) {
     'outer: loop {
@@ -54,6 +123,13 @@ pub fn handle_usrsigs(
                 SIGUSR2 => {
                     debug!("{}(): Recd SIGUSR2", func_name!());
                     s.send(BlMode::On).unwrap();
+                    d.send(DisplayCmd::Wake).unwrap();
+                }
+                SIGHUP => {
+                    info!("{}(): Recd SIGHUP, reloading config", func_name!());
+                    if config_reload(config_path.as_deref(), &config) {
+                        d.send(DisplayCmd::ConfigReloaded).unwrap();
+                    }
                 }
                 _ => {}
             }
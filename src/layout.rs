@@ -0,0 +1,676 @@
+//! Screen layouts described in a TOML file: rows and columns of boxes
+//! with padding, backgrounds and borders, holding text, icons and rules.
+//! Text can bind to the stats sent to the remote displays, "{UPTIME}",
+//! "{CPU_TEMP}", "{BTC_CMP_STR}" etc., filled in on every frame.
+//!
+//! Boxes are laid out along their parent, rows left to right and columns
+//! top to bottom. A box with a `size` gets that many pixels, text gets
+//! its line height, and whatever is left is shared by `weight`.
+//!
+//! layout.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 19-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::fontfile::*;
+use crate::fonts::font8::*;
+use crate::fonts::font12::*;
+use crate::fonts::font16::*;
+use crate::fonts::font20::*;
+use crate::fonts::font24::*;
+use crate::lcd::lcd::*;
+use crate::sprite::*;
+use crate::stats::STAT_KEYS;
+use crate::text::*;
+use crate::typeset::*;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The dashboard as it was before layouts
+const DEFAULT_LAYOUT: &str = include_str!("layouts/dashboard.toml");
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Read(PathBuf, std::io::Error),
+    Parse(String, toml::de::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Read(path, e) => write!(f, "can't read {}: {}", path.display(), e),
+            LayoutError::Parse(name, e) => write!(f, "can't parse {}: {}", name, e),
+            LayoutError::Invalid(errors) => write!(f, "invalid layout: {}", errors.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// RGB565 colour, named ("orange") or "#RRGGBB" in the file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Colour(pub UWORD);
+
+const COLOUR_NAMES: [(&str, UWORD); 18] = [
+    ("white", WHITE),
+    ("black", BLACK),
+    ("blue", BLUE),
+    ("bred", BRED),
+    ("gred", GRED),
+    ("gblue", GBLUE),
+    ("red", RED),
+    ("magenta", MAGENTA),
+    ("green", GREEN),
+    ("cyan", CYAN),
+    ("yellow", YELLOW),
+    ("brown", BROWN),
+    ("brred", BRRED),
+    ("gray", GRAY),
+    ("tangarine", TANGARINE),
+    ("orange", ORANGE),
+    ("red2", RED2),
+    ("blue2", BLUE2),
+];
+
+impl TryFrom<String> for Colour {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let name = s.to_lowercase();
+        if let Some((_, colour)) = COLOUR_NAMES.iter().find(|(n, _)| *n == name) {
+            return Ok(Colour(*colour));
+        }
+        match name
+            .strip_prefix('#')
+            .map(|hex| u32::from_str_radix(hex, 16))
+        {
+            Some(Ok(rgb)) if name.len() == 7 => Ok(Colour(rgb_to_u16(
+                (rgb >> 16) as u8,
+                (rgb >> 8) as u8,
+                rgb as u8,
+            ))),
+            _ => Err(format!(
+                "unknown colour \"{}\", expected \"#RRGGBB\" or one of {:?}",
+                s,
+                COLOUR_NAMES.map(|(n, _)| n)
+            )),
+        }
+    }
+}
+
+impl From<Colour> for String {
+    fn from(colour: Colour) -> Self {
+        match COLOUR_NAMES.iter().find(|(_, c)| *c == colour.0) {
+            Some((name, _)) => name.to_string(),
+            None => {
+                let c = colour.0 as u32;
+                let rgb = ((c >> 11) & 0x1F) << 19 | ((c >> 5) & 0x3F) << 10 | (c & 0x1F) << 3;
+                format!("#{:06X}", rgb)
+            }
+        }
+    }
+}
+
+/// A built-in font, or one named in the config file's [fonts]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum FontName {
+    Font8,
+    #[default]
+    Font12,
+    Font16,
+    Font20,
+    Font24,
+    Aa12, // anti-aliased
+    Aa16,
+    Aa24,
+    Aa25,
+    File(String),
+}
+
+const FONT_NAMES: [(&str, FontName); 9] = [
+    ("font8", FontName::Font8),
+    ("font12", FontName::Font12),
+    ("font16", FontName::Font16),
+    ("font20", FontName::Font20),
+    ("font24", FontName::Font24),
+    ("aa12", FontName::Aa12),
+    ("aa16", FontName::Aa16),
+    ("aa24", FontName::Aa24),
+    ("aa25", FontName::Aa25),
+];
+
+impl From<String> for FontName {
+    fn from(name: String) -> Self {
+        FONT_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map_or(FontName::File(name), |(_, font)| font.clone())
+    }
+}
+
+impl From<FontName> for String {
+    fn from(font: FontName) -> Self {
+        match font {
+            FontName::File(name) => name,
+            font => FONT_NAMES
+                .iter()
+                .find(|(_, f)| *f == font)
+                .map(|(n, _)| n.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl FontName {
+    /// None for font files
+    pub fn builtin(&self) -> Option<&'static dyn Font> {
+        Some(match self {
+            FontName::Font8 => &FONT8,
+            FontName::Font12 => &FONT12,
+            FontName::Font16 => &FONT16,
+            FontName::Font20 => &FONT20,
+            FontName::Font24 => &FONT24,
+            FontName::Aa12 => &*AA_FONT12,
+            FontName::Aa16 => &*AA_FONT16,
+            FontName::Aa24 => &*AA_FONT24,
+            FontName::Aa25 => &*AA_FONT25,
+            FontName::File(_) => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconName {
+    Wifi,
+    Temp,
+    Battery,
+    Bitcoin,
+}
+
+impl IconName {
+    pub fn sprite(self) -> &'static Sprite {
+        match self {
+            IconName::Wifi => &ICONS.wifi,
+            IconName::Temp => &ICONS.temp,
+            IconName::Battery => &ICONS.battery,
+            IconName::Bitcoin => &ICONS.bitcoin,
+        }
+    }
+}
+
+/// One value for all sides, [vertical, horizontal] or [top, right,
+/// bottom, left], as in CSS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(try_from = "PaddingSpec")]
+pub struct Padding {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Padding {
+    fn inset(&self, (x, y, w, h): Rect) -> Rect {
+        (
+            x + self.left,
+            y + self.top,
+            w.saturating_sub(self.left + self.right),
+            h.saturating_sub(self.top + self.bottom),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PaddingSpec {
+    All(usize),
+    Sides(Vec<usize>),
+}
+
+impl TryFrom<PaddingSpec> for Padding {
+    type Error = String;
+
+    fn try_from(spec: PaddingSpec) -> Result<Self, Self::Error> {
+        let (top, right, bottom, left) = match spec {
+            PaddingSpec::All(n) => (n, n, n, n),
+            PaddingSpec::Sides(v) => match v[..] {
+                [n] => (n, n, n, n),
+                [y, x] => (y, x, y, x),
+                [t, r, b, l] => (t, r, b, l),
+                _ => return Err("padding takes 1, 2 or 4 values".to_string()),
+            },
+        };
+        Ok(Self {
+            top,
+            right,
+            bottom,
+            left,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    #[default]
+    Column,
+    Row,
+    Text,
+    Rule,   // solid line of `colour`, across the parent
+    Spacer, // empty, takes up room
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Node {
+    #[serde(rename = "type")]
+    pub kind: NodeKind,
+    pub size: Option<usize>, // pixels along the parent's direction
+    pub weight: usize,       // share of the room left over
+    pub padding: Padding,
+    pub background: Option<Colour>,
+    pub border: Option<Colour>, // on the outermost pixels, the rest is inside it
+    pub text: String,           // "{KEY}" is replaced by the stat, "{{" and "}}" are braces
+    pub font: FontName,
+    pub proportional: bool,
+    pub colour: Colour,
+    pub align: Align,
+    pub icon: Option<IconName>, // left of the text
+    pub children: Vec<Node>,
+    #[serde(skip)]
+    file_font: Option<Arc<BitmapFont>>, // loaded for FontName::File
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            kind: NodeKind::Column,
+            size: None,
+            weight: 1,
+            padding: Padding::default(),
+            background: None,
+            border: None,
+            text: String::new(),
+            font: FontName::Font12,
+            proportional: false,
+            colour: Colour(WHITE),
+            align: Align::Left,
+            icon: None,
+            children: Vec::new(),
+            file_font: None,
+        }
+    }
+}
+
+/// A screen: a column of boxes filling the display
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default = "Layout::empty", deny_unknown_fields)]
+pub struct Layout {
+    pub background: Colour,
+    pub padding: Padding,
+    pub children: Vec<Node>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::from_toml("built-in layout", DEFAULT_LAYOUT, &BTreeMap::new())
+            .expect("Error: built-in layout")
+    }
+}
+
+// (x, y, w, h)
+type Rect = (usize, usize, usize, usize);
+
+impl Layout {
+    // What a layout file leaves out, Default is the built-in dashboard
+    fn empty() -> Self {
+        Self {
+            background: Colour(BLACK),
+            padding: Padding::default(),
+            children: Vec::new(),
+        }
+    }
+
+    /// `fonts` are the font files the layout can name, from [fonts] in
+    /// the config file
+    pub fn from_toml(
+        name: &str,
+        text: &str,
+        fonts: &BTreeMap<String, PathBuf>,
+    ) -> Result<Self, LayoutError> {
+        let mut layout: Layout =
+            toml::from_str(text).map_err(|e| LayoutError::Parse(name.to_string(), e))?;
+        layout.validate()?;
+        layout.load_fonts(fonts)?;
+        Ok(layout)
+    }
+
+    pub fn load(path: &Path, fonts: &BTreeMap<String, PathBuf>) -> Result<Self, LayoutError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| LayoutError::Read(path.to_path_buf(), e))?;
+        Self::from_toml(&path.display().to_string(), &text, fonts)
+    }
+
+    /// `path` if given, the built-in dashboard otherwise
+    pub fn load_or_default(
+        path: Option<&Path>,
+        fonts: &BTreeMap<String, PathBuf>,
+    ) -> Result<Self, LayoutError> {
+        match path {
+            Some(path) => Self::load(path, fonts),
+            None => Ok(Self::default()),
+        }
+    }
+
+    // Each font file once, however many boxes use it
+    fn load_fonts(&mut self, fonts: &BTreeMap<String, PathBuf>) -> Result<(), LayoutError> {
+        let mut loaded = HashMap::new();
+        let mut errors = Vec::new();
+        for (i, node) in self.children.iter_mut().enumerate() {
+            node.load_fonts(&format!("children[{}]", i), fonts, &mut loaded, &mut errors);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LayoutError::Invalid(errors))
+        }
+    }
+
+    /// Unknown stats, text options on boxes and the like, all problems
+    /// reported together
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        let mut errors = Vec::new();
+        for (i, node) in self.children.iter().enumerate() {
+            node.validate(&format!("children[{}]", i), &mut errors);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LayoutError::Invalid(errors))
+        }
+    }
+
+    /// Draw the layout with `stats` filled in, from get_json_obj()
+    pub fn draw<B: DisplayBus>(&self, l: &mut Lcd<B>, stats: &json::JsonValue) {
+        let (w, h) = l.img_size();
+        let canvas = (0, 0, w, h);
+        let inner = self.padding.inset(canvas);
+        draw_children(
+            l,
+            &self.children,
+            false,
+            canvas,
+            inner,
+            self.background.0,
+            stats,
+        );
+    }
+}
+
+impl Node {
+    fn font(&self) -> &dyn Font {
+        match &self.file_font {
+            Some(font) => font.as_ref(),
+            None => self.font.builtin().unwrap_or(&FONT12),
+        }
+    }
+
+    fn load_fonts(
+        &mut self,
+        path: &str,
+        fonts: &BTreeMap<String, PathBuf>,
+        loaded: &mut HashMap<String, Arc<BitmapFont>>,
+        errors: &mut Vec<String>,
+    ) {
+        if let FontName::File(name) = &self.font {
+            if let Some(font) = loaded.get(name) {
+                self.file_font = Some(font.clone());
+            } else {
+                match fonts.get(name).map(|file| BitmapFont::load(file)) {
+                    Some(Ok(font)) => {
+                        let font = Arc::new(font);
+                        loaded.insert(name.clone(), font.clone());
+                        self.file_font = Some(font);
+                    }
+                    Some(Err(e)) => errors.push(format!("{}.font: {}", path, e)),
+                    None => errors.push(format!(
+                        "{}.font: \"{}\" is not built in or in [fonts]",
+                        path, name
+                    )),
+                }
+            }
+        }
+        for (i, child) in self.children.iter_mut().enumerate() {
+            child.load_fonts(&format!("{}.children[{}]", path, i), fonts, loaded, errors);
+        }
+    }
+
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        let is_box = matches!(self.kind, NodeKind::Column | NodeKind::Row);
+        if !is_box && !self.children.is_empty() {
+            errors.push(format!("{}: only rows and columns have children", path));
+        }
+        if self.kind != NodeKind::Text && (!self.text.is_empty() || self.icon.is_some()) {
+            errors.push(format!("{}: text and icon need type = \"text\"", path));
+        }
+        if let Err(e) = expand(&self.text, |key| STAT_KEYS.contains(&key).then(String::new)) {
+            errors.push(format!("{}.text: {}", path, e));
+        }
+        for (i, child) in self.children.iter().enumerate() {
+            child.validate(&format!("{}.children[{}]", path, i), errors);
+        }
+    }
+
+    // Fixed length along the parent, None to share what is left
+    fn fixed_size(&self, horizontal: bool) -> Option<usize> {
+        self.size.or(match self.kind {
+            NodeKind::Text if !horizontal => {
+                Some(self.font().height() + self.padding.top + self.padding.bottom)
+            }
+            NodeKind::Rule => Some(1),
+            _ => None,
+        })
+    }
+
+    // Each pixel is written once a frame, with what it ends up as, so a
+    // redraw of the same stats leaves nothing to flush
+    fn draw<B: DisplayBus>(&self, l: &mut Lcd<B>, rect: Rect, bg: UWORD, stats: &json::JsonValue) {
+        let (x, y, w, h) = rect;
+        let bg = self.background.map_or(bg, |c| c.0);
+        let mut area = rect;
+        let mut inner = self.padding.inset(rect);
+
+        // On the outermost pixels, the rest is drawn inside it
+        if let Some(border) = self.border
+            && w > 0
+            && h > 0
+        {
+            let (x1, y1) = ((x + w - 1) as i32, (y + h - 1) as i32);
+            for (a, b) in [
+                ((x as i32, y as i32), (x1, y as i32)),
+                ((x1, y as i32), (x1, y1)),
+                ((x1, y1), (x as i32, y1)),
+                ((x as i32, y1), (x as i32, y as i32)),
+            ] {
+                l.img_draw_line(a.0, a.1, b.0, b.1, border.0);
+            }
+            area = (x + 1, y + 1, w.saturating_sub(2), h.saturating_sub(2));
+            inner = intersect(inner, area);
+        }
+
+        match self.kind {
+            NodeKind::Column | NodeKind::Row => {
+                let horizontal = self.kind == NodeKind::Row;
+                draw_children(l, &self.children, horizontal, area, inner, bg, stats);
+            }
+            NodeKind::Text => {
+                let holes = self.draw_text(l, inner, bg, stats);
+                l.img_draw_rect_around(area, &holes, bg);
+            }
+            NodeKind::Rule => {
+                let (ix, iy, iw, ih) = inner;
+                l.img_draw_rect(ix, iy, iw, ih, self.colour.0);
+                l.img_draw_rect_around(area, &[inner], bg);
+            }
+            NodeKind::Spacer => {
+                let (ax, ay, aw, ah) = area;
+                l.img_draw_rect(ax, ay, aw, ah, bg);
+            }
+        }
+    }
+
+    // Icon on the left, text aligned in the rest, both on the first line.
+    // Returns the boxes drawn, the rest of `rect` is left to the caller.
+    fn draw_text<B: DisplayBus>(
+        &self,
+        l: &mut Lcd<B>,
+        rect: Rect,
+        bg: UWORD,
+        stats: &json::JsonValue,
+    ) -> Vec<Rect> {
+        let (mut x, y, mut w, _) = rect;
+        let font = self.font();
+        let mut drawn = Vec::new();
+        if let Some(icon) = self.icon {
+            let sprite = icon.sprite();
+            let (iw, ih) = sprite.size();
+            let iy = y as i32 + (font.height() as i32 - ih as i32) / 2;
+            l.img_draw_bitmap_on(x as i32, iy, sprite, bg);
+            let top = iy.max(0) as usize;
+            drawn.push((x, top, iw, (iy + ih as i32).max(0) as usize - top));
+            x += iw + 2;
+            w = w.saturating_sub(iw + 2);
+        }
+        drawn.push((x, y, w, font.height()));
+        let text = expand(&self.text, |key| Some(stat(stats, key))).unwrap_or_default();
+        let (x, y) = (x as i32, y as i32);
+        let colour = self.colour.0;
+        if self.proportional {
+            let font = Proportional::new(font);
+            l.img_draw_text_aligned(x, y, w, &text, &font, colour, Some(bg), self.align);
+        } else {
+            l.img_draw_text_aligned(x, y, w, &text, font, colour, Some(bg), self.align);
+        }
+        drawn
+    }
+}
+
+// `children` split along `rect`, and `bg` in the rest of `area` around
+// them
+fn draw_children<B: DisplayBus>(
+    l: &mut Lcd<B>,
+    children: &[Node],
+    horizontal: bool,
+    area: Rect,
+    rect: Rect,
+    bg: UWORD,
+    stats: &json::JsonValue,
+) {
+    let (x, y, w, h) = rect;
+    let mut pos = if horizontal { x } else { y };
+    let rects: Vec<Rect> = split(if horizontal { w } else { h }, children, horizontal)
+        .into_iter()
+        .map(|size| {
+            let rect = if horizontal {
+                (pos, y, size, h)
+            } else {
+                (x, pos, w, size)
+            };
+            pos += size;
+            rect
+        })
+        .collect();
+    l.img_draw_rect_around(area, &rects, bg);
+    for (child, rect) in children.iter().zip(rects) {
+        child.draw(l, rect, bg, stats);
+    }
+}
+
+// The overlap of two rectangles, empty if they don't
+fn intersect(a: Rect, b: Rect) -> Rect {
+    let (x0, y0) = (a.0.max(b.0), a.1.max(b.1));
+    let (x1, y1) = ((a.0 + a.2).min(b.0 + b.2), (a.1 + a.3).min(b.1 + b.3));
+    (x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0))
+}
+
+// Lengths of `children` along a `total` long parent, fixed sizes first,
+// the rest shared by weight with the last flexible child taking the
+// rounding
+fn split(total: usize, children: &[Node], horizontal: bool) -> Vec<usize> {
+    let fixed: usize = children
+        .iter()
+        .filter_map(|c| c.fixed_size(horizontal))
+        .sum();
+    let mut left = total.saturating_sub(fixed);
+    let mut weights: usize = children
+        .iter()
+        .filter(|c| c.fixed_size(horizontal).is_none())
+        .map(|c| c.weight)
+        .sum();
+    children
+        .iter()
+        .map(|c| match c.fixed_size(horizontal) {
+            Some(size) => size,
+            None => {
+                let size = (left * c.weight).checked_div(weights).unwrap_or(0);
+                left -= size;
+                weights -= c.weight;
+                size
+            }
+        })
+        .collect()
+}
+
+// Stat as text, strings without their quotes, missing ones empty
+pub(crate) fn stat(stats: &json::JsonValue, key: &str) -> String {
+    match &stats[key] {
+        json::JsonValue::Null => String::new(),
+        value => value.as_str().map_or_else(|| value.dump(), String::from),
+    }
+}
+
+/// `text` with each "{KEY}" replaced by `lookup(KEY)`, an error for
+/// unknown keys and unmatched braces
+pub fn expand(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut key = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => key.push(c),
+                        None => return Err("unmatched \"{\"".to_string()),
+                    }
+                }
+                match lookup(&key) {
+                    Some(value) => out.push_str(&value),
+                    None => return Err(format!("unknown stat \"{}\"", key)),
+                }
+            }
+            '}' => return Err("unmatched \"}\"".to_string()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
@@ -7,32 +7,39 @@
 //!
 //!
 
-/// This needs clean-up. The LCD display documentation is not clear
-/// on what is happening in the Y direction... somehow it's working.
-/// Is there something obvious that's missing here?
+/// This needs clean-up. The image buffer is a plain row-major canvas of
+/// big-endian RGB565 pixels, sized by the panel profile and orientation.
+#[allow(clippy::module_inception)]
 pub mod lcd {
+    use crate::bus::*;
     use crate::defs::*;
-    use crate::gpio::*;
-    use crate::spi::*;
+    use crate::glyphs::*;
+    use crate::pages::{Overlay, PageCmd};
+    use crate::panel::*;
     use log::{LevelFilter, debug, error, info, warn};
     use rppal::gpio::Level;
     use std::iter::*;
-
-    impl Lcd {
-        pub fn new(cs_pin: UBYTE, dc_pin: UBYTE, rst_pin: UBYTE, bl_pin: UBYTE) -> Self {
-            Self {
-                cs_pin,
-                dc_pin,
-                rst_pin,
-                bl_pin,
-                orientation: LcdOrientation::Rotate0,
+    use std::time::Instant;
+
+    impl<B: DisplayBus> Lcd<B> {
+        pub fn new(bus: B, panel: &'static PanelProfile) -> Self {
+            let mut lcd = Self {
+                bus,
+                panel,
+                orientation: panel.orientation,
                 max_buffer_size: 32,
-                image: [0x00; IMG_ARR_SIZE],
-            }
+                img_width: 0,
+                img_height: 0,
+                image: Vec::new(),
+                dirty: Vec::new(),
+            };
+            lcd.img_resize();
+            lcd
         }
 
         pub fn with_orientation(mut self, orientation: LcdOrientation) -> Self {
             self.orientation = orientation;
+            self.img_resize();
             self
         }
 
@@ -41,65 +48,160 @@ pub mod lcd {
             self
         }
 
-        fn size(&self) -> (usize, usize) {
-            match self.orientation {
-                LcdOrientation::Rotate0 => (LCD_WIDTH, LCD_HEIGHT),
-                LcdOrientation::Rotate90 => (LCD_HEIGHT, LCD_WIDTH),
-                LcdOrientation::Rotate180 => (LCD_WIDTH, LCD_HEIGHT),
-                LcdOrientation::Rotate270 => (LCD_HEIGHT, LCD_WIDTH),
-            }
+        pub fn bus(&self) -> &B {
+            &self.bus
+        }
+
+        pub fn bus_mut(&mut self) -> &mut B {
+            &mut self.bus
+        }
+
+        /// Give the bus back, e.g. to drive it with another panel profile
+        pub fn into_bus(self) -> B {
+            self.bus
         }
 
-        fn set_adjustment(&self) -> (u8, u8) {
+        pub fn panel(&self) -> &'static PanelProfile {
+            self.panel
+        }
+
+        pub fn orientation(&self) -> LcdOrientation {
+            self.orientation
+        }
+
+        /// Panel size in the current orientation, row/column exchange (MV)
+        /// swaps width and height
+        pub fn size(&self) -> (usize, usize) {
             if self.memory_access_control_value() & 0x20 != 0x20 {
-                (LCD_X_CORRECTION, LCD_Y_CORRECTION)
+                (self.panel.width, self.panel.height)
             } else {
-                (LCD_Y_CORRECTION, LCD_X_CORRECTION)
+                (self.panel.height, self.panel.width)
+            }
+        }
+
+        /// Reprogram the scan direction and resize the canvas to match. The
+        /// panel is cleared, the caller redraws.
+        pub fn set_orientation(&mut self, orientation: LcdOrientation) -> Result<(), LcdError> {
+            if orientation == self.orientation {
+                return Ok(());
             }
+            self.orientation = orientation;
+            self.img_resize();
+            self.bus
+                .write_cmd_data(MEMORY_ACCESS_CONTROL, &[self.memory_access_control_value()])?;
+            self.lcd_clear(BLACK)?;
+
+            info!(
+                "{}(): {:?}, {}x{}",
+                func_name!(),
+                orientation,
+                self.img_width,
+                self.img_height
+            );
+            Ok(())
+        }
+
+        /// Display off and the controller asleep, the GRAM is kept
+        pub fn lcd_sleep(&mut self) -> Result<(), LcdError> {
+            self.bus.write_cmd(DISPLAY_OFF)?;
+            self.bus.write_cmd(ENTER_SLEEP_MODE)?;
+            self.bus.delay_ms(5);
+            debug!("{}()", func_name!());
+            Ok(())
+        }
+
+        /// Back from lcd_sleep(), showing what was there
+        pub fn lcd_wake(&mut self) -> Result<(), LcdError> {
+            self.bus.write_cmd(SLEEP_OUT)?;
+            self.bus.delay_ms(120);
+            self.bus.write_cmd(DISPLAY_ON)?;
+            debug!("{}()", func_name!());
+            Ok(())
+        }
+
+        fn set_adjustment(&self) -> (u16, u16) {
+            self.panel.offset(self.orientation)
         }
 
         fn memory_access_control_value(&self) -> UBYTE {
-            let orientation = match self.orientation {
-                LcdOrientation::Rotate0 => 0b00000000,
-                LcdOrientation::Rotate90 => 0b01100000,
-                LcdOrientation::Rotate180 => 0b11000000,
-                LcdOrientation::Rotate270 => 0b10100000,
-            };
-            orientation | 0b00001000
+            self.panel.madctl_value(self.orientation)
         }
 
-        pub fn lcd_init(&mut self) {
-            gpio_write2(self.bl_pin, Level::High).unwrap();
-            gpio_sleep_200_ms();
-            gpio_write2(self.cs_pin, Level::High).unwrap();
-            gpio_sleep_200_ms();
-            gpio_write2(self.cs_pin, Level::Low).unwrap();
-            gpio_sleep_200_ms();
-            gpio_write2(self.rst_pin, Level::High).unwrap();
-            gpio_sleep_200_ms();
-            gpio_write2(self.rst_pin, Level::Low).unwrap();
-            gpio_sleep_200_ms();
-            gpio_write2(self.rst_pin, Level::High).unwrap();
-            gpio_sleep_200_ms();
+        // Each command goes out with all of its parameters in one transfer
+        fn write_seq(&mut self, sequence: &[CmdOrData]) -> Result<(), LcdError> {
+            let mut cmd: Option<UBYTE> = None;
+            let mut params: Vec<UBYTE> = Vec::new();
+            for item in sequence {
+                match item {
+                    CmdOrData::Cmd(c) => {
+                        if let Some(prev) = cmd {
+                            self.bus.write_cmd_data(prev, &params)?;
+                        }
+                        cmd = Some(*c);
+                        params.clear();
+                    }
+                    CmdOrData::Data(data) => params.push(*data),
+                    CmdOrData::Delay(ms) => {
+                        if let Some(prev) = cmd.take() {
+                            self.bus.write_cmd_data(prev, &params)?;
+                        }
+                        params.clear();
+                        self.bus.delay_ms(*ms as u64);
+                    }
+                }
+            }
+            if let Some(prev) = cmd {
+                self.bus.write_cmd_data(prev, &params)?;
+            }
+            Ok(())
+        }
+
+        pub fn lcd_init(&mut self) -> Result<(), LcdError> {
+            self.bus.set_pin(LcdPin::Bl, Level::High)?;
+            self.bus.delay_ms(200);
+            self.bus.set_pin(LcdPin::Cs, Level::High)?;
+            self.bus.delay_ms(200);
+            self.bus.set_pin(LcdPin::Cs, Level::Low)?;
+            self.bus.delay_ms(200);
+            self.bus.set_pin(LcdPin::Rst, Level::High)?;
+            self.bus.delay_ms(200);
+            self.bus.set_pin(LcdPin::Rst, Level::Low)?;
+            self.bus.delay_ms(200);
+            self.bus.set_pin(LcdPin::Rst, Level::High)?;
+            self.bus.delay_ms(200);
 
-            spi_write_seq(&LCD_INIT_SEQ);
+            let start = Instant::now();
 
-            spi_write_ubyte2(&CmdOrData::Cmd(0xB4)); // Column inversion
-            spi_write_ubyte2(&CmdOrData::Data(0x07));
+            self.write_seq(self.panel.init_seq)?;
 
-            spi_write_seq(&LCD_GAMMA_SEQ);
+            self.write_seq(self.panel.post_init_seq)?;
 
-            spi_write_ubyte2(&CmdOrData::Cmd(MEMORY_ACCESS_CONTROL));
-            spi_write_ubyte2(&CmdOrData::Data(self.memory_access_control_value()));
+            self.write_seq(self.panel.gamma_seq)?;
 
-            self.lcd_set_window(0, 0, LCD_WIDTH, LCD_HEIGHT).unwrap();
+            self.bus
+                .write_cmd_data(MEMORY_ACCESS_CONTROL, &[self.memory_access_control_value()])?;
+
+            let (w, h) = self.size();
+            self.lcd_set_window(0, 0, w, h)?;
 
-            spi_write_ubyte2(&CmdOrData::Cmd(MEMORY_WRITE));
+            self.bus.write_cmd(MEMORY_WRITE)?;
 
-            self.lcd_clear(BLACK).unwrap();
+            self.lcd_clear(BLACK)?;
+
+            debug!(
+                "{}(): {} {}x{}, {:?}, done in {:?}",
+                func_name!(),
+                self.panel.name,
+                w,
+                h,
+                self.orientation,
+                start.elapsed()
+            );
 
             // self.img_clear(BLACK);
-            // self.img_draw_image(0, 0, IMG_WIDTH, IMG_HEIGHT * 2);
+            // self.img_draw_image(0, 0, w, h);
+
+            Ok(())
         }
 
         pub fn lcd_set_window(
@@ -109,24 +211,24 @@ pub mod lcd {
             x1: usize,
             y1: usize,
         ) -> Result<(), LcdError> {
+            let (x_adj, y_adj) = self.set_adjustment();
+
             let c1 = x1.saturating_sub(1).max(x0);
             let p1 = y1.saturating_sub(1).max(y0);
-            let (c0h, c0l) = u16_to_bytes(x0 as u16);
-            let (c1h, c1l) = u16_to_bytes(c1 as u16);
-            let (p0h, p0l) = u16_to_bytes(y0 as u16);
-            let (p1h, p1l) = u16_to_bytes(p1 as u16);
-
-            let (x_adj, y_adj) = self.set_adjustment();
+            let (c0h, c0l) = u16_to_bytes(x0 as u16 + x_adj);
+            let (c1h, c1l) = u16_to_bytes(c1 as u16 + x_adj);
+            let (p0h, p0l) = u16_to_bytes(y0 as u16 + y_adj);
+            let (p1h, p1l) = u16_to_bytes(p1 as u16 + y_adj);
 
-            spi_write_ubyte2(&CmdOrData::Cmd(PARTIAL_MODE_OFF));
+            self.bus.write_cmd(PARTIAL_MODE_OFF)?;
 
-            spi_write_ubyte2(&CmdOrData::Cmd(COLUMN_ADDRESS_SET));
-            spi_write_data_array(&[c0h, c0l + x_adj, c1h, c1l + x_adj]);
+            self.bus
+                .write_cmd_data(COLUMN_ADDRESS_SET, &[c0h, c0l, c1h, c1l])?;
 
-            spi_write_ubyte2(&CmdOrData::Cmd(ROW_ADDRESS_SET));
-            spi_write_data_array(&[p0h, p0l + y_adj, p1h, p1l + y_adj]);
+            self.bus
+                .write_cmd_data(ROW_ADDRESS_SET, &[p0h, p0l, p1h, p1l])?;
 
-            spi_write_ubyte2(&CmdOrData::Cmd(MEMORY_WRITE));
+            self.bus.write_cmd(MEMORY_WRITE)?;
 
             Ok(())
         }
@@ -141,29 +243,139 @@ pub mod lcd {
         ) -> Result<(), LcdError> {
             self.lcd_set_window(x, y, x + w, y + h)?;
 
-            let chunk = color_buffer::<32>(colour);
-            for _ in 0..(w as u32 * h as u32).div_ceil(16) {
-                spi_write_data_array(&chunk);
-            }
+            let (ch, cl) = u16_to_bytes(colour);
+            let buf: Vec<u8> = repeat_n([ch, cl], w * h).flatten().collect();
+            self.bus.write_data(&buf)?;
             Ok(())
         }
 
         pub fn lcd_clear(&mut self, colour: UWORD) -> Result<(), LcdError> {
             let (w, h) = self.size();
             self.lcd_fill_rect(0, 0, w, h, colour)?;
+            self.img_invalidate();
 
             Ok(())
         }
 
         /* image functions */
+
+        /// Canvas size in pixels, (x, y) maps one-to-one to panel pixels
+        pub fn img_size(&self) -> (usize, usize) {
+            (self.img_width, self.img_height)
+        }
+
+        // Size the canvas for the current orientation, contents are lost
+        fn img_resize(&mut self) {
+            (self.img_width, self.img_height) = self.size();
+            self.image = vec![0x00; self.img_width * self.img_height * LCD_COLOUR_DEPTH];
+            self.dirty = vec![None; self.img_height];
+        }
+
         pub fn img_clear(&mut self, colour: UWORD) -> &Self {
-            for i in (0..IMG_ARR_SIZE).step_by(2) {
-                self.image[i] = ((colour >> 8) & 0xFF) as u8;
-                self.image[i + 1] = (colour & 0xFF) as u8;
+            for j in 0..self.img_height {
+                for i in 0..self.img_width {
+                    self.img_set_pixel(i, j, colour);
+                }
             }
             self
         }
 
+        // Write a pixel known to be on the canvas, recording it as dirty
+        // only if it actually changed.
+        fn img_set_pixel(&mut self, x: usize, y: usize, colour: UWORD) {
+            let idx = y * self.img_width * LCD_COLOUR_DEPTH + x * LCD_COLOUR_DEPTH;
+            let (h, l) = u16_to_bytes(colour);
+            if self.image[idx] == h && self.image[idx + 1] == l {
+                return;
+            }
+            self.image[idx] = h;
+            self.image[idx + 1] = l;
+            self.dirty[y] = match self.dirty[y] {
+                Some((x0, x1)) => Some((x0.min(x), x1.max(x + 1))),
+                None => Some((x, x + 1)),
+            };
+        }
+
+        /// Mark the whole canvas as changed, next `flush()` sends everything
+        pub fn img_invalidate(&mut self) {
+            self.dirty.fill(Some((0, self.img_width)));
+        }
+
+        /// Changed areas as (x0, y0, x1, y1) windows, end exclusive. Rows are
+        /// merged while the extra (unchanged) pixels that come along stay
+        /// under DIRTY_MERGE_WASTE_PX, too many windows collapse into one.
+        pub fn img_dirty_rects(&self) -> Vec<(usize, usize, usize, usize)> {
+            let mut rects = Vec::new();
+            let mut cur: Option<((usize, usize, usize, usize), usize)> = None;
+
+            for (y, span) in self.dirty.iter().enumerate() {
+                let Some((a, b)) = *span else {
+                    continue;
+                };
+                cur = match cur {
+                    Some(((x0, y0, x1, _), used)) => {
+                        let (ux0, ux1) = (x0.min(a), x1.max(b));
+                        let area = (ux1 - ux0) * (y + 1 - y0);
+                        if area - (used + b - a) <= DIRTY_MERGE_WASTE_PX {
+                            Some(((ux0, y0, ux1, y + 1), used + b - a))
+                        } else {
+                            rects.push(cur.unwrap().0);
+                            Some(((a, y, b, y + 1), b - a))
+                        }
+                    }
+                    None => Some(((a, y, b, y + 1), b - a)),
+                };
+            }
+            if let Some((rect, _)) = cur {
+                rects.push(rect);
+            }
+
+            if rects.len() > DIRTY_MAX_RECTS {
+                let bbox = rects.iter().fold(rects[0], |acc, r| {
+                    (
+                        acc.0.min(r.0),
+                        acc.1.min(r.1),
+                        acc.2.max(r.2),
+                        acc.3.max(r.3),
+                    )
+                });
+                rects = vec![bbox];
+            }
+            rects
+        }
+
+        /// Send only the changed parts of the canvas to the panel
+        pub fn flush(&mut self) -> Result<usize, LcdError> {
+            let start = Instant::now();
+            let rects = self.img_dirty_rects();
+            let mut bytes = 0;
+            let mut buf: Vec<u8> = Vec::with_capacity(self.image.len());
+
+            for &(x0, y0, x1, y1) in &rects {
+                buf.clear();
+                for j in y0..y1 {
+                    let row = j * self.img_width * LCD_COLOUR_DEPTH;
+                    buf.extend_from_slice(
+                        &self.image[row + x0 * LCD_COLOUR_DEPTH..row + x1 * LCD_COLOUR_DEPTH],
+                    );
+                }
+                self.lcd_set_window(x0, y0, x1, y1)?;
+                self.bus.write_data(&buf)?;
+                bytes += buf.len();
+            }
+            self.dirty.fill(None);
+
+            debug!(
+                "{}(): {} window(s), {} bytes in {:?} {:?}",
+                func_name!(),
+                rects.len(),
+                bytes,
+                start.elapsed(),
+                rects
+            );
+            Ok(bytes)
+        }
+
         pub fn img_draw_rect(
             &mut self,
             x: usize,
@@ -172,11 +384,34 @@ pub mod lcd {
             h: usize,
             colour: UWORD,
         ) -> &Self {
-            for j in (y..(y + h)).step_by(2) {
-                for i in (x..(x + w)).step_by(1) {
-                    self.image[j * IMG_WIDTH + (i * LCD_COLOUR_DEPTH)] =
-                        ((colour >> 8) & 0xFF) as u8;
-                    self.image[j * IMG_WIDTH + (i * LCD_COLOUR_DEPTH) + 1] = (colour & 0xFF) as u8;
+            let x_end = (x + w).min(self.img_width);
+            let y_end = (y + h).min(self.img_height);
+            for j in y..y_end {
+                for i in x..x_end {
+                    self.img_set_pixel(i, j, colour);
+                }
+            }
+            self
+        }
+
+        /// Rectangle (x, y, w, h) less the `holes` in it, for a background
+        /// around things drawn with their own, so no pixel is written twice
+        pub fn img_draw_rect_around(
+            &mut self,
+            (x, y, w, h): (usize, usize, usize, usize),
+            holes: &[(usize, usize, usize, usize)],
+            colour: UWORD,
+        ) -> &Self {
+            let x_end = (x + w).min(self.img_width);
+            let y_end = (y + h).min(self.img_height);
+            for j in y..y_end {
+                for i in x..x_end {
+                    let in_hole = holes.iter().any(|&(hx, hy, hw, hh)| {
+                        (hx..hx + hw).contains(&i) && (hy..hy + hh).contains(&j)
+                    });
+                    if !in_hole {
+                        self.img_set_pixel(i, j, colour);
+                    }
                 }
             }
             self
@@ -190,30 +425,46 @@ pub mod lcd {
             h: usize,
             colour: UWORD,
         ) -> &Self {
-            for j in (y..(y + h)).step_by(2) {
-                for i in (x..(x + w)).step_by(1) {
+            for j in y..(y + h) {
+                for i in x..(x + w) {
                     self.img_draw_pixel(i, j, colour);
                 }
             }
             self
         }
 
+        /// Pixels outside the canvas are silently clipped
         pub fn img_draw_pixel(&mut self, x: usize, y: usize, colour: UWORD) -> &Self {
-            let j = y.div_ceil(2) * 2;
-            // if j >= LCD_HEIGHT {
-            //     return self;
-            // }
-            self.image[j * IMG_WIDTH + x * LCD_COLOUR_DEPTH] = ((colour >> 8) & 0xFF) as u8;
-            self.image[j * IMG_WIDTH + x * LCD_COLOUR_DEPTH + 1] = (colour & 0xFF) as u8;
+            if x >= self.img_width || y >= self.img_height {
+                return self;
+            }
+            self.img_set_pixel(x, y, colour);
 
             self
         }
 
-        // same as img_draw_pixel(), don't know WTH is happening
-        pub fn img_draw_pixel_font(&mut self, x: usize, y: usize, colour: UWORD) -> &Self {
-            if y % 2 == 0 {
-                self.image[y * IMG_WIDTH + x * LCD_COLOUR_DEPTH] = ((colour >> 8) & 0xFF) as u8;
-                self.image[y * IMG_WIDTH + x * LCD_COLOUR_DEPTH + 1] = (colour & 0xFF) as u8;
+        /// Colour at (x, y), None outside the canvas
+        pub fn img_get_pixel(&self, x: usize, y: usize) -> Option<UWORD> {
+            if x >= self.img_width || y >= self.img_height {
+                return None;
+            }
+            let idx = y * self.img_width * LCD_COLOUR_DEPTH + x * LCD_COLOUR_DEPTH;
+            Some(((self.image[idx] as UWORD) << 8) | self.image[idx + 1] as UWORD)
+        }
+
+        /// Mix `colour` into the pixel at (x, y), `alpha` 0 leaves it as is
+        /// and 255 replaces it
+        pub fn img_blend_pixel(&mut self, x: usize, y: usize, colour: UWORD, alpha: u8) -> &Self {
+            match alpha {
+                0 => {}
+                255 => {
+                    self.img_draw_pixel(x, y, colour);
+                }
+                _ => {
+                    if let Some(bg) = self.img_get_pixel(x, y) {
+                        self.img_set_pixel(x, y, alpha_blend(colour, bg, alpha));
+                    }
+                }
             }
             self
         }
@@ -227,12 +478,12 @@ pub mod lcd {
             colour_fg: UWORD,
             colour_bg: UWORD,
         ) {
-            if c as u8 == 0 {
+            if c == '\0' {
                 error!("{}(): Char is NULL, exiting", func_name!());
                 return;
             }
 
-            if x > LCD_WIDTH || y > LCD_HEIGHT * 2 {
+            if x >= self.img_width || y >= self.img_height {
                 error!(
                     "{}(): x value [{}] or y value [{}] is out of bounds, exiting",
                     func_name!(),
@@ -242,23 +493,25 @@ pub mod lcd {
                 return;
             }
 
-            let mut char_offset: usize = (c as usize - ' ' as usize)
-                * font.height
-                * (font.width / 8 + (if font.width % 8 != 0 { 1 } else { 0 }));
+            let Some(data) = table_glyph(font, c).or_else(|| table_glyph(font, REPLACEMENT_CHAR))
+            else {
+                return;
+            };
+            let mut char_offset: usize = 0;
 
-            for j in (0..font.height).step_by(1) {
+            for j in 0..font.height {
                 for i in 0..font.width {
                     let pos = 0x80 >> (i % 8);
-                    if (font.table[char_offset] & pos) != 0 {
-                        self.img_draw_pixel_font(x + i, y + j * 2, colour_fg);
+                    if (data[char_offset] & pos) != 0 {
+                        self.img_draw_pixel(x + i, y + j, colour_fg);
                     } else {
-                        self.img_draw_pixel_font(x + i, y + j * 2, colour_bg);
+                        self.img_draw_pixel(x + i, y + j, colour_bg);
                     }
-                    if i % 8 == 7 {
+                    if (i + 1).is_multiple_of(8) {
                         char_offset += 1
                     }
                 }
-                if font.width % 8 != 0 {
+                if !font.width.is_multiple_of(8) {
                     char_offset += 1
                 }
             }
@@ -289,6 +542,30 @@ pub mod lcd {
             }
         }
 
+        /// Colour bars on the top half, red, green, blue and grey
+        /// gradients below them
+        pub fn img_draw_test_pattern(&mut self) -> &Self {
+            const BARS: [UWORD; 8] = [WHITE, YELLOW, CYAN, GREEN, MAGENTA, RED, BLUE, BLACK];
+            let (w, h) = (self.img_width, self.img_height);
+            for (i, colour) in BARS.iter().enumerate() {
+                let x0 = i * w / BARS.len();
+                let x1 = (i + 1) * w / BARS.len();
+                self.img_draw_rect(x0, 0, x1 - x0, h / 2, *colour);
+            }
+
+            let band = (h - h / 2) / 4;
+            for i in 0..w {
+                let v = (i * 255 / (w - 1).max(1)) as u8;
+                let ramps = [(v, 0, 0), (0, v, 0), (0, 0, v), (v, v, v)];
+                for (n, (r, g, b)) in ramps.iter().enumerate() {
+                    let y0 = h / 2 + n * band;
+                    let y1 = if n == 3 { h } else { y0 + band };
+                    self.img_draw_rect(i, y0, 1, y1 - y0, rgb_to_u16(*r, *g, *b));
+                }
+            }
+            self
+        }
+
         pub fn img_draw_image(
             &mut self,
             x_start: usize,
@@ -301,7 +578,10 @@ pub mod lcd {
             let chunks = self.image.chunks((x_end - x_start) * LCD_COLOUR_DEPTH);
 
             for chunk in chunks {
-                spi_write_data_array(chunk);
+                if let Err(e) = self.bus.write_data(chunk) {
+                    error!("{}(): {:?}", func_name!(), e);
+                    break;
+                }
             }
 
             self
@@ -309,12 +589,12 @@ pub mod lcd {
 
         // print array for debugging
         pub fn img_print_data(&self) {
-            let mut chunks = self.image.chunks(IMG_WIDTH * LCD_COLOUR_DEPTH);
+            let mut chunks = self.image.chunks(self.img_width * LCD_COLOUR_DEPTH);
 
             loop {
                 match chunks.next() {
                     Some(c) => {
-                        for item in c.iter().take(IMG_WIDTH * LCD_COLOUR_DEPTH) {
+                        for item in c.iter().take(self.img_width * LCD_COLOUR_DEPTH) {
                             print!("0x{:02X} ", item);
                         }
                         println!("\n");
@@ -328,7 +608,9 @@ pub mod lcd {
         }
     }
 
-    pub const IMG_ARR_SIZE: usize = IMG_WIDTH * IMG_HEIGHT * LCD_COLOUR_DEPTH;
+    /// Unchanged pixels worth re-sending to save a window set-up
+    pub const DIRTY_MERGE_WASTE_PX: usize = 64;
+    pub const DIRTY_MAX_RECTS: usize = 16;
 
     pub const WHITE: UWORD = 0xFFFF;
     pub const BLACK: UWORD = 0x0000;
@@ -349,7 +631,7 @@ pub mod lcd {
     pub const RED2: UWORD = 0xF841;
     pub const BLUE2: UWORD = 0x0E3F;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
     pub enum LcdOrientation {
         Rotate0,
         Rotate90,
@@ -357,16 +639,57 @@ pub mod lcd {
         Rotate270,
     }
 
-    pub const LCD_ORIENTATION: LcdOrientation = LcdOrientation::Rotate90;
+    impl LcdOrientation {
+        /// Index into the per-orientation tables of `PanelProfile`
+        pub fn index(self) -> usize {
+            match self {
+                LcdOrientation::Rotate0 => 0,
+                LcdOrientation::Rotate90 => 1,
+                LcdOrientation::Rotate180 => 2,
+                LcdOrientation::Rotate270 => 3,
+            }
+        }
+
+        pub fn from_degrees(degrees: u16) -> Option<Self> {
+            match degrees % 360 {
+                0 => Some(LcdOrientation::Rotate0),
+                90 => Some(LcdOrientation::Rotate90),
+                180 => Some(LcdOrientation::Rotate180),
+                270 => Some(LcdOrientation::Rotate270),
+                _ => None,
+            }
+        }
 
-    pub struct Lcd {
-        cs_pin: UBYTE,
-        dc_pin: UBYTE,  // Data / Command - 0=WriteCommand, 1=WriteData
-        rst_pin: UBYTE, // Reset
-        bl_pin: UBYTE,  // Backlight PWM
+        pub fn degrees(self) -> u16 {
+            self.index() as u16 * 90
+        }
+
+        /// Same orientation, turned by `degrees` (a multiple of 90)
+        pub fn rotated(self, degrees: u16) -> Self {
+            Self::from_degrees((self.degrees() + degrees / 90 * 90) % 360).unwrap()
+        }
+    }
+
+    /// Requests to the main loop that change how the panel is driven
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum DisplayCmd {
+        Flip, // rotate 180 degrees, for HATs mounted upside down
+        SetOrientation(LcdOrientation),
+        ConfigReloaded,
+        Page(PageCmd),    // joystick
+        Overlay(Overlay), // over the page until a key is pressed
+        Wake,             // a key, or SIGUSR2
+    }
+
+    pub struct Lcd<B: DisplayBus> {
+        bus: B,
+        panel: &'static PanelProfile,
         orientation: LcdOrientation,
         max_buffer_size: usize,
-        image: [u8; IMG_ARR_SIZE],
+        img_width: usize,
+        img_height: usize,
+        image: Vec<u8>,
+        dirty: Vec<Option<(usize, usize)>>, // changed x span per row, end exclusive
     }
 
     // REF: https://github.com/maciekglowka/lcd-ili9341-spi/blob/main/src/utils.rs
@@ -382,11 +705,38 @@ pub mod lcd {
         (rb as u16) << 11 | (gb as u16) << 5 | bb as u16
     }
 
+    /// Mix two 565 RGB colours, `alpha` 0 (all `bg`) to 255 (all `fg`)
+    pub fn alpha_blend(fg: u16, bg: u16, alpha: u8) -> u16 {
+        let a = alpha as u32;
+        let mix = |shift: u32, mask: u32| {
+            let (f, b) = ((fg as u32 >> shift) & mask, (bg as u32 >> shift) & mask);
+            ((f * a + b * (255 - a) + 127) / 255) << shift
+        };
+        (mix(11, 0x1F) | mix(5, 0x3F) | mix(0, 0x1F)) as u16
+    }
+
+    /// Same for 4-bit alpha, 0 to 15
+    pub fn alpha_blend4(fg: u16, bg: u16, alpha: u8) -> u16 {
+        alpha_blend(fg, bg, alpha.min(15) * 17)
+    }
+
     /// Combine RGB channels into 565 RGB format - as a (u8, u8) tuple
     pub fn rgb_to_u8(r: u8, g: u8, b: u8) -> (u8, u8) {
         u16_to_bytes(rgb_to_u16(r, g, b))
     }
 
+    /// Split a 565 RGB colour into 8-bit channels
+    pub fn u16_to_rgb(colour: u16) -> (u8, u8, u8) {
+        let r = ((colour >> 11) & 0x1F) as u8;
+        let g = ((colour >> 5) & 0x3F) as u8;
+        let b = (colour & 0x1F) as u8;
+        (
+            (r << 3) | (r >> 2),
+            (g << 2) | (g >> 4),
+            (b << 3) | (b >> 2),
+        )
+    }
+
     /// Create a single colored buffer of N/2 pixel length
     pub fn color_buffer<const N: usize>(color: u16) -> [u8; N] {
         let (h, l) = u16_to_bytes(color);
@@ -436,190 +786,7 @@ pub mod lcd {
     pub enum CmdOrData {
         Cmd(UBYTE),
         Data(UBYTE),
+        Delay(UWORD), // ms, after the command before it
     }
     use CmdOrData::*;
-
-    pub const LCD_INIT_SEQ: [CmdOrData; 47] = [
-        Cmd(SLEEP_OUT),
-        Cmd(POWER_CONTROL_B),
-        Data(0x00),
-        Data(0xC1),
-        Data(0x30),
-        Cmd(POWER_ON_SEQ_CONTROL),
-        Data(0x64),
-        Data(0x03),
-        Data(0x12),
-        Data(0x81),
-        Cmd(DRIVER_TIMING_CONTROL_A),
-        Data(0x85),
-        Data(0x00),
-        Data(0x79),
-        Cmd(POWER_CONTROL_A),
-        Data(0x39),
-        Data(0x2C),
-        Data(0x00),
-        Data(0x34),
-        Data(0x02),
-        Cmd(PUMP_RATIO_CONTROL),
-        Data(0x20),
-        Cmd(DRIVER_TIMING_CONTROL_B),
-        Data(0x00),
-        Data(0x00),
-        Cmd(POWER_CONTROL_1),
-        Data(0x1D),
-        Cmd(POWER_CONTROL_2),
-        Data(0x12),
-        Cmd(VCOM_CONTROL_1),
-        Data(0x33),
-        Data(0x3F),
-        Cmd(VCOM_CONTROL_2),
-        Data(0x92),
-        Cmd(PIXEL_FORMAT_SET),
-        Data(0x05), // 0x03 or 0x05 or 0x06?
-        Cmd(MEMORY_ACCESS_CONTROL),
-        Data(0x08),
-        Cmd(FRAME_CONTROL_NORMAL_MODE),
-        Data(0x00),
-        Data(0x12),
-        Cmd(DISPLAY_FUNCTION_CONTROL),
-        Data(0x0A),
-        Data(0xA2),
-        Cmd(SET_TEAR_SCANLINE),
-        Data(0x00),
-        Cmd(DISPLAY_ON),
-    ];
-
-    pub const LCD_GAMMA_SEQ: [CmdOrData; 36] = [
-        Cmd(ENABLE_3G),
-        Data(0x00),
-        Cmd(GAMMA_SET),
-        Data(0x01),
-        Cmd(POSITIVE_GAMMA_CORRECTION),
-        Data(0x0F),
-        Data(0x22),
-        Data(0x1C),
-        Data(0x1B),
-        Data(0x08),
-        Data(0x0F),
-        Data(0x48),
-        Data(0xB8),
-        Data(0x34),
-        Data(0x05),
-        Data(0x0C),
-        Data(0x09),
-        Data(0x0F),
-        Data(0x07),
-        Data(0x00),
-        Cmd(NEGATIVE_GAMMA_CORRECTION),
-        Data(0x00),
-        Data(0x23),
-        Data(0x24),
-        Data(0x07),
-        Data(0x10),
-        Data(0x07),
-        Data(0x38),
-        Data(0x47),
-        Data(0x4B),
-        Data(0x0A),
-        Data(0x13),
-        Data(0x06),
-        Data(0x30),
-        Data(0x38),
-        Data(0x0F),
-    ];
-
-    // alternate initializing sequence
-    pub const LCD_INIT_SEQ2: [CmdOrData; 76] = [
-        Cmd(0xB1),
-        Data(0x01),
-        Data(0x2C),
-        Data(0x2D),
-        //
-        Cmd(0xB2),
-        Data(0x01),
-        Data(0x2C),
-        Data(0x2D),
-        //
-        Cmd(0xB3),
-        Data(0x01),
-        Data(0x2C),
-        Data(0x2D),
-        Data(0x01),
-        Data(0x2C),
-        Data(0x2D),
-        //
-        Cmd(0xB4), // Column inversion
-        Data(0x07),
-        //
-        Cmd(0xC0), // ST7735R Power Sequence
-        Data(0xA2),
-        Data(0x02),
-        Data(0x84),
-        //
-        Cmd(0xC1),
-        Data(0xC5),
-        //
-        Cmd(0xC2),
-        Data(0x0A),
-        Data(0x00),
-        //
-        Cmd(0xC3),
-        Data(0x8A),
-        Data(0x2A),
-        //
-        Cmd(0xC4),
-        Data(0x8A),
-        Data(0xEE),
-        //
-        Cmd(0xC5), // VCOM
-        Data(0x0E),
-        //
-        Cmd(0xE0), // ST7735R Gamma Sequence
-        Data(0x0F),
-        Data(0x1A),
-        Data(0x0F),
-        Data(0x18),
-        Data(0x2F),
-        Data(0x28),
-        Data(0x20),
-        Data(0x22),
-        Data(0x1F),
-        Data(0x1B),
-        Data(0x23),
-        Data(0x37),
-        Data(0x00),
-        Data(0x07),
-        Data(0x02),
-        Data(0x10),
-        //
-        Cmd(0xE1),
-        Data(0x0F),
-        Data(0x1B),
-        Data(0x0F),
-        Data(0x17),
-        Data(0x33),
-        Data(0x2C),
-        Data(0x29),
-        Data(0x2e),
-        Data(0x30),
-        Data(0x30),
-        Data(0x39),
-        Data(0x3F),
-        Data(0x00),
-        Data(0x07),
-        Data(0x03),
-        Data(0x10),
-        //
-        Cmd(0xF0), // Enable test command
-        Data(0x01),
-        //
-        Cmd(0xF6), // Disable ram power save mode
-        Data(0x00),
-        //
-        Cmd(0x3A), // 65k mode
-        Data(0x05),
-        //
-        Cmd(0x11),
-        Cmd(0x29),
-    ];
 }
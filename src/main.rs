@@ -6,45 +6,84 @@
 //! 30-May-2025
 //!
 
+mod bindings;
+mod bus;
+mod chart;
+mod cli;
+mod config;
 mod crypto;
 mod defs;
+mod draw;
+mod fontfile;
 mod fonts;
+mod glyphs;
 mod gpio;
+mod graphics;
+mod history;
 mod http;
+mod idle;
+mod input;
 mod keys;
+mod layout;
 mod lcd;
+mod pages;
+mod panel;
 mod pwm;
+mod schedule;
+mod sim;
 mod spi;
+mod sprite;
 mod stats;
+mod text;
+mod typeset;
 mod usb;
 mod utils;
 
+use crate::bindings::*;
+use crate::bus::*;
+use crate::chart::*;
+use crate::cli::*;
+use crate::config::*;
 use crate::crypto::*;
 use crate::defs::*;
 use crate::fonts::font8::*;
 use crate::fonts::font12::*;
 use crate::fonts::font16::*;
+use crate::history::*;
 use crate::http::http_server;
+use crate::idle::*;
 use crate::keys::*;
+use crate::layout::Layout;
 use crate::lcd::lcd::*;
+use crate::pages::*;
+use crate::panel::*;
 use crate::pwm::*;
-use crate::usb::usb_thd;
+use crate::schedule::*;
+use crate::sim::SimBus;
+use crate::sprite::ICONS;
+use crate::stats::get_json_obj;
+use crate::typeset::measure_text;
+use crate::usb::{usb_probe, usb_thd};
 use crate::utils::*;
+use clap::Parser;
 use crossbeam_channel::unbounded;
 use log::{LevelFilter, debug, error, info, warn};
 use signal_hook::consts::TERM_SIGNALS;
 use signal_hook::consts::*;
 use signal_hook::flag;
 use signal_hook::iterator::Signals;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
-use systemd_journal_logger::JournalLog;
+use std::time::{Duration, Instant};
 use tokio::runtime::Builder;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let command = cli.command.clone().unwrap_or(Command::Run);
+
     let exe_name = std::env::current_exe()
         .expect("Can't get the exec path")
         .file_name()
@@ -52,19 +91,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .to_string_lossy()
         .into_owned();
 
-    JournalLog::new()
-        .unwrap()
-        .with_extra_fields(vec![("VERSION", env!("CARGO_PKG_VERSION"))])
-        .with_syslog_identifier(
-            // String::from(exe_name.clone()))
-            systemd_journal_logger::current_exe_identifier()
-                .expect("Error: systemd_journal_logger::current_exe_identifier()")
-                .to_string(),
-        )
-        .install()
-        .unwrap();
-    log::set_max_level(LevelFilter::Info);
+    // Only the daemon logs to the journal
+    init_logging(cli.log_level, cli.stderr_log || command != Command::Run);
+
+    let config = match Config::load_or_default(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("[{exe_name}] {e}");
+            eprintln!("{exe_name}: {e}");
+            std::process::exit(1);
+        }
+    };
+    debug!("[{exe_name}] {:?}", config);
+
+    match command {
+        Command::Run => run(&exe_name, cli.config, config),
+        Command::Render { out, page } => render(&config, &out, page),
+        Command::Stats { json } => {
+            print_stats(json);
+            Ok(())
+        }
+        Command::Schedule { days } => {
+            print_schedule(&config.schedule, days);
+            Ok(())
+        }
+        Command::UsbProbe => {
+            for line in usb_probe(&config.usb)? {
+                println!("{line}");
+            }
+            Ok(())
+        }
+        Command::TestPattern { out, secs } => test_pattern(&config, out.as_deref(), secs),
+    }
+}
 
+/// The daemon
+fn run(
+    exe_name: &str,
+    config_path: Option<PathBuf>,
+    mut config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("[{exe_name}] started");
 
     // Trap Ctrl-C, other exit signals
@@ -74,31 +140,82 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         flag::register(*sig, Arc::clone(&term_now))?;
     }
 
-    let mut sigusr_signals = Signals::new([SIGUSR1, SIGUSR2])?;
+    let mut sigusr_signals = Signals::new([SIGUSR1, SIGUSR2, SIGHUP])?;
 
     // Flag(s) to signal thread loops to exit
     let exit_flag = Arc::new(Mutex::new(false));
     let exit_flag_pwm = exit_flag.clone();
+    let exit_flag_sched = exit_flag.clone();
     let exit_flag_kchk = exit_flag.clone();
     let exit_usb_thd = exit_flag.clone();
     let exit_crypto_thd = exit_flag.clone();
     let exit_usrsigs_thd = exit_flag.clone();
+    let exit_history_thd = exit_flag.clone();
 
     let (s1, r1) = unbounded::<BlMode>(); // keys_check(), bl_pwm()
     let _s2 = s1.clone(); // forward signals to bl_pwm()
+    let s3 = s1.clone(); // bl_schedule()
+    let s4 = s1.clone(); // main loop, idle timeout
 
     let (c_s1, r_s1) = unbounded::<CryptoResult>(); // crypto_thd()
 
+    let (d_s1, d_r1) = unbounded::<DisplayCmd>(); // keys_check(), main loop
+
+    let (cr_s1, cr_r1) = unbounded::<()>(); // keys_check(), crypto_thd() refresh now
+    let d_s2 = d_s1.clone(); // handle_usrsigs()
+
     let crypto_result = Arc::new(Mutex::new(CryptoResult::new_empty())); // crypto_thd()
     let crypto_result1 = crypto_result.clone(); // http_server()
     let crypto_result2 = crypto_result.clone(); // usb_thd()
-
-    let key_chk_thread: thread::JoinHandle<()> = thread::spawn(|| keys_check(s1, exit_flag_kchk));
-    let pwm_thread: thread::JoinHandle<()> = thread::spawn(|| bl_pwm(r1, exit_flag_pwm));
+    let crypto_result3 = crypto_result.clone(); // main loop
+
+    let shared_config: SharedConfig = Arc::new(Mutex::new(config.clone()));
+    let shared_config1 = shared_config.clone(); // usb_thd()
+    let shared_config2 = shared_config.clone(); // http_server()
+    let shared_config3 = shared_config.clone(); // crypto_thd()
+    let shared_config4 = shared_config.clone(); // handle_usrsigs()
+    let shared_config5 = shared_config.clone(); // history_thd()
+    let shared_config6 = shared_config.clone(); // keys_check()
+    let shared_config7 = shared_config.clone(); // bl_pwm()
+    let shared_config8 = shared_config.clone(); // bl_schedule()
+
+    let history = Arc::new(Mutex::new(History::from_config(&config.history))); // history_thd()
+    let history1 = history.clone(); // main loop
+
+    let idle_state = Arc::new(Mutex::new(IdleState::Awake)); // main loop
+    let idle_state1 = idle_state.clone(); // keys_check()
+    let last_key = Arc::new(Mutex::new(Instant::now())); // main loop
+    let last_key1 = last_key.clone(); // keys_check()
+
+    let key_chk_thread: thread::JoinHandle<()> = thread::spawn(move || {
+        keys_check(
+            shared_config6,
+            s1,
+            d_s1,
+            cr_s1,
+            idle_state1,
+            last_key1,
+            exit_flag_kchk,
+        )
+    });
+    let pwm_thread: thread::JoinHandle<()> =
+        thread::spawn(move || bl_pwm(shared_config7, r1, exit_flag_pwm));
+    let schedule_thread: thread::JoinHandle<()> =
+        thread::spawn(move || bl_schedule(shared_config8, s3, exit_flag_sched));
     let usb_thread: thread::JoinHandle<()> =
-        thread::spawn(|| usb_thd(exit_usb_thd, crypto_result2));
-    let usrsig_thread: thread::JoinHandle<()> =
-        thread::spawn(move || handle_usrsigs(_s2, &mut sigusr_signals, exit_usrsigs_thd));
+        thread::spawn(move || usb_thd(shared_config1, exit_usb_thd, crypto_result2));
+    let history_thread: thread::JoinHandle<()> =
+        thread::spawn(move || history_thd(shared_config5, history, exit_history_thd));
+    let usrsig_thread: thread::JoinHandle<()> = thread::spawn(move || {
+        handle_usrsigs(
+            _s2,
+            d_s2,
+            &mut sigusr_signals,
+            config_path,
+            shared_config4,
+            exit_usrsigs_thd,
+        )
+    });
 
     // These blocking threads require tokio::rt
     let rt = Builder::new_multi_thread()
@@ -108,23 +225,165 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .worker_threads(2) // TWO threads
         .build()
         .unwrap();
-    let http_server_thread = rt.spawn(async { http_server(crypto_result1) });
-    let crypto_thread = rt.spawn(crypto_thd(c_s1, exit_crypto_thd, crypto_result));
-
-    let mut l = Lcd::new(LCD_CS, LCD_DC, LCD_RST, LCD_BL)
-        .with_orientation(LCD_ORIENTATION)
-        .with_max_buffer_size(64);
-
-    l.lcd_init();
-
-    // Don't know where to put this
-    let mut btc: String = String::from("waiting...");
+    let http_server_thread = rt.spawn(async { http_server(shared_config2, crypto_result1) });
+    let crypto_thread = rt.spawn(crypto_thd(
+        shared_config3,
+        c_s1,
+        cr_r1,
+        exit_crypto_thd,
+        crypto_result,
+    ));
+
+    let panel = config.panel();
+    info!("[{exe_name}] Panel: {}", panel.description);
+
+    let p = &config.pins;
+    let mut l = Lcd::new(
+        RppalBus::new(p.lcd_cs, p.lcd_dc, p.lcd_rst, p.lcd_bl)
+            .expect("Error: Could not open LCD bus"),
+        panel,
+    )
+    .with_orientation(config.orientation())
+    .with_max_buffer_size(64);
+
+    l.lcd_init().expect("Error: lcd_init()");
+
+    let mut layout = Layout::load_or_default(config.screen.layout.as_deref(), &config.fonts)?;
+    let mut pager = Pager::new(&config.pages);
+    pager.show(config.screen.view.into());
+    let mut idle = Idle::new(&config.idle);
+    let mut sleep_at: Option<Instant> = None; // once the backlight has faded out
 
     // MAIN LOOP
     while !term_now.load(Ordering::Relaxed) {
-        lcd_display_stuff(&mut l, &r_s1, &mut btc);
+        if let Ok(crypto_result) = r_s1.try_recv() {
+            crypto_result.print();
+        }
+
+        // An alert keeps the panel awake while it lasts
+        let now = Instant::now();
+        idle.touch(*last_key.lock().unwrap());
+        let alert = history1.lock().unwrap().latest().and_then(|sample| {
+            config
+                .idle
+                .alerts
+                .iter()
+                .find(|alert| alert.firing(sample))
+                .cloned()
+        });
+        let idle_changed = match alert {
+            Some(alert) => idle.activity(now).inspect(|_| {
+                info!(
+                    "[{exe_name}] {} above {}",
+                    alert.metric.label(),
+                    alert.metric.format(alert.above)
+                )
+            }),
+            None => idle.tick(now),
+        };
+        if let Some(state) = idle_changed {
+            sleep_at = set_idle(&mut l, state, &s4, &config, &idle_state);
+        }
+        if let Some(at) = sleep_at
+            && at <= now
+        {
+            sleep_at = None;
+            if let Err(e) = l.lcd_sleep() {
+                error!("[{exe_name}] lcd_sleep(): {:?}", e);
+            }
+        }
+
+        pager.tick();
+        if pager.take_changed() {
+            l.img_clear(BLACK);
+        }
+        if idle.state() != IdleState::Off {
+            let stats = get_json_obj(crypto_result3.clone());
+            match pager.page() {
+                _ if pager.has_overlay() => lcd_display_page(&mut l, &mut pager, &stats),
+                Page::Overview => lcd_display_stuff(&mut l, &layout, &stats),
+                Page::Chart => lcd_display_chart(&mut l, &history1.lock().unwrap(), &config.chart),
+                _ => lcd_display_page(&mut l, &mut pager, &stats),
+            }
+        }
 
-        thread::sleep(Duration::from_secs(SCREEN_UPDATE_INTERVAL_SECS));
+        // Sleep, but wake up early for a display command, to turn the page,
+        // to close an overlay or to time out
+        let mut timeout = Duration::from_secs(config.screen.update_interval_secs);
+        if let Some(tick) = pager.until_tick() {
+            timeout = timeout.min(tick);
+        }
+        if let Some(tick) = idle.until_tick(Instant::now()) {
+            timeout = timeout.min(tick);
+        }
+        if let Some(at) = sleep_at {
+            timeout = timeout.min(at.saturating_duration_since(Instant::now()));
+        }
+        if let Ok(cmd) = d_r1.recv_timeout(timeout) {
+            let orientation = match cmd {
+                DisplayCmd::Flip => Some(l.orientation().rotated(180)),
+                DisplayCmd::SetOrientation(o) => Some(o),
+                DisplayCmd::ConfigReloaded => {
+                    let new = shared_config.lock().unwrap().clone();
+                    let orientation_changed = new.screen.orientation != config.screen.orientation
+                        || new.screen.flip != config.screen.flip;
+                    if new.screen.panel != config.screen.panel {
+                        // Different controller, start over on the same bus
+                        info!("[{exe_name}] Panel: {}", new.panel().description);
+                        l = Lcd::new(l.into_bus(), new.panel())
+                            .with_orientation(new.orientation())
+                            .with_max_buffer_size(64);
+                        if let Err(e) = l.lcd_init() {
+                            error!("[{exe_name}] lcd_init(): {:?}", e);
+                        }
+                        if idle.state() == IdleState::Off
+                            && let Err(e) = l.lcd_sleep()
+                        {
+                            error!("[{exe_name}] lcd_sleep(): {:?}", e);
+                        }
+                    }
+                    // The layout file may have changed on its own
+                    match Layout::load_or_default(new.screen.layout.as_deref(), &new.fonts) {
+                        Ok(new_layout) => layout = new_layout,
+                        Err(e) => error!("[{exe_name}] {e}, keeping the old layout"),
+                    }
+                    pager.reconfigure(&new.pages);
+                    idle.reconfigure(&new.idle);
+                    if new.screen.view != config.screen.view {
+                        pager.show(new.screen.view.into());
+                    }
+                    if new.chart != config.chart {
+                        l.img_clear(BLACK);
+                    }
+                    config = new;
+                    orientation_changed.then(|| config.orientation())
+                }
+                DisplayCmd::Page(cmd) => {
+                    // The joystick turns with the panel
+                    let upright = config.panel().orientation.degrees();
+                    let cmd = cmd.rotated((l.orientation().degrees() + 360 - upright) % 360);
+                    if let Some(power) = pager.handle(cmd) {
+                        power.run();
+                    }
+                    None
+                }
+                DisplayCmd::Overlay(overlay) => {
+                    pager.show_overlay(overlay);
+                    None
+                }
+                DisplayCmd::Wake => {
+                    if let Some(state) = idle.activity(Instant::now()) {
+                        sleep_at = set_idle(&mut l, state, &s4, &config, &idle_state);
+                    }
+                    None
+                }
+            };
+            if let Some(orientation) = orientation
+                && let Err(e) = l.set_orientation(orientation)
+            {
+                error!("[{exe_name}] set_orientation(): {:?}", e);
+            }
+        }
     }
 
     info!("[{exe_name}] Stopping threads...");
@@ -154,6 +413,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    match history_thread.join() {
+        Ok(_) => {
+            info!("history_thd() thread ended");
+        }
+        Err(e) => {
+            error!("Error stopping history_thd() thread {:?}", e);
+        }
+    }
+
     match usb_thread.join() {
         Ok(_) => {
             info!("usb_thd() thread ended");
@@ -163,6 +431,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    match schedule_thread.join() {
+        Ok(_) => {
+            info!("bl_schedule() thread ended");
+        }
+        Err(e) => {
+            error!("Error stopping bl_schedule() thread {:?}", e);
+        }
+    }
+
     match pwm_thread.join() {
         Ok(_) => {
             info!("bl_pwm() thread ended");
@@ -186,104 +463,198 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn lcd_display_stuff(
-    l: &mut Lcd,
-    r_s1: &crossbeam_channel::Receiver<CryptoResult>,
-    btc: &mut String,
-) {
-    l.lcd_set_window(0, 0, IMG_WIDTH, IMG_HEIGHT).unwrap();
+/// Backlight and controller for the new idle state, `idle_state` tells
+/// keys_check() whether a key has to wake the panel. Going off, returns
+/// when the backlight will have faded out and the controller can sleep.
+fn set_idle<B: DisplayBus>(
+    l: &mut Lcd<B>,
+    state: IdleState,
+    s: &crossbeam_channel::Sender<BlMode>,
+    config: &Config,
+    idle_state: &Mutex<IdleState>,
+) -> Option<Instant> {
+    info!("{}(): {:?}", func_name!(), state);
+    let was = std::mem::replace(&mut *idle_state.lock().unwrap(), state);
+    if was == IdleState::Off
+        && state != IdleState::Off
+        && let Err(e) = l.lcd_wake()
+    {
+        error!("{}(): lcd_wake(): {:?}", func_name!(), e);
+    }
+    match state {
+        IdleState::Awake => s.send(BlMode::Wake).unwrap(),
+        IdleState::Dimmed => s.send(BlMode::Dim(config.idle.dim_percent)).unwrap(),
+        IdleState::Off => {
+            // Fade out before the picture goes
+            s.send(BlMode::Dim(0)).unwrap();
+            return Some(Instant::now() + Duration::from_millis(config.backlight.fade_ms));
+        }
+    }
+    None
+}
 
-    l.img_draw_rect2(0, 0, IMG_WIDTH, 32, WHITE);
-    l.img_draw_string(
-        &((IMG_WIDTH - get_time_str().len() * FONT12.width) / 2),
-        &(8),
-        &(get_time_str()),
-        &FONT12,
-        BLACK,
-        WHITE,
-    );
+/// One frame of `page`, or the configured view, on the simulated panel,
+/// written to `out`
+fn render(
+    config: &Config,
+    out: &Path,
+    page: Option<Page>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut l = Lcd::new(SimBus::for_panel(config.panel()), config.panel())
+        .with_orientation(config.orientation());
+    l.lcd_init().map_err(|e| format!("lcd_init(): {:?}", e))?;
+
+    let page = page.unwrap_or(config.screen.view.into());
+    let stats = get_json_obj(Arc::new(Mutex::new(CryptoResult::new_empty())));
+    match page {
+        Page::Overview => {
+            let layout = Layout::load_or_default(config.screen.layout.as_deref(), &config.fonts)?;
+            lcd_display_stuff(&mut l, &layout, &stats);
+        }
+        Page::Chart => {
+            // No history yet, just the one sample
+            let mut history = History::from_config(&config.history);
+            history.push(Sampler::new().sample());
+            lcd_display_chart(&mut l, &history, &config.chart);
+        }
+        _ => {
+            // Any page, listed or not
+            let mut pager = Pager::new(&PagesConfig {
+                list: vec![page],
+                ..config.pages.clone()
+            });
+            lcd_display_page(&mut l, &mut pager, &stats);
+        }
+    }
 
-    l.img_draw_string(&(4), &(42), "IP Address", &FONT8, BLUE2, BLACK);
-    l.img_draw_rect2(0, 42 + 24, IMG_WIDTH, FONT12.height, BLACK);
-    l.img_draw_string(
-        &((IMG_WIDTH - get_ip().len() * FONT12.width) - 4),
-        &(42 + 24),
-        &(get_ip()),
-        &FONT12,
-        WHITE,
-        BLACK,
-    );
-    l.img_draw_rect2(0, 42 + 24 + 2 + 2 + FONT12.height * 2, IMG_WIDTH, 1, ORANGE);
+    l.bus().write_image(out)
+}
 
-    let (_, uptime, load, temp) = get_cpu_info();
-    l.img_draw_string(&(4), &(102), "Uptime", &FONT8, BLUE2, BLACK);
-    l.img_draw_rect2(0, 102 + 24, IMG_WIDTH, FONT12.height, BLACK);
-    l.img_draw_string(
-        &((IMG_WIDTH - uptime.len() * FONT12.width) - 4),
-        &(102 + 24),
-        &(uptime),
-        &FONT12,
-        WHITE,
-        BLACK,
-    );
-    l.img_draw_rect2(
-        0,
-        102 + 24 + 2 + 2 + FONT12.height * 2,
-        IMG_WIDTH,
-        1,
-        ORANGE,
-    );
+fn print_stats(json: bool) {
+    let stats = get_json_obj(Arc::new(Mutex::new(CryptoResult::new_empty())));
+    if json {
+        println!("{}", stats.dump());
+    } else {
+        for (key, value) in stats.entries() {
+            println!("{:<26} {}", key, value);
+        }
+    }
+}
+
+/// The level now and the transitions over the next `days` days
+fn print_schedule(schedule: &ScheduleConfig, days: u64) {
+    let now = chrono::Local::now();
+    let line = |t: &Transition, note: &str| {
+        let rule = &schedule.rules[t.rule];
+        let mut at = rule.at.to_string();
+        if rule.offset_mins != 0 {
+            at += &format!(" {:+}m", rule.offset_mins);
+        }
+        println!(
+            "{}  {:>3}%  {}{}",
+            t.at.format("%a %d-%b %H:%M"),
+            t.percent,
+            at,
+            note
+        );
+    };
 
-    l.img_draw_string(&(4), &(162), "Load", &FONT8, BLUE2, BLACK);
+    match current_transition(schedule, now) {
+        Some(t) => line(&t, "  (now)"),
+        None => println!("No schedule"),
+    }
+    transitions(schedule, now.date_naive(), days)
+        .iter()
+        .filter(|t| t.at > now)
+        .for_each(|t| line(t, ""));
+}
+
+/// Test pattern on the panel, or into a file with the simulated panel
+fn test_pattern(
+    config: &Config,
+    out: Option<&Path>,
+    secs: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let panel = config.panel();
+    if let Some(out) = out {
+        let mut l =
+            Lcd::new(SimBus::for_panel(panel), panel).with_orientation(config.orientation());
+        l.lcd_init().map_err(|e| format!("lcd_init(): {:?}", e))?;
+        l.img_draw_test_pattern();
+        l.flush().map_err(|e| format!("flush(): {:?}", e))?;
+        return l.bus().write_image(out);
+    }
+
+    let p = &config.pins;
+    let mut l = Lcd::new(
+        RppalBus::new(p.lcd_cs, p.lcd_dc, p.lcd_rst, p.lcd_bl)?,
+        panel,
+    )
+    .with_orientation(config.orientation());
+    l.lcd_init().map_err(|e| format!("lcd_init(): {:?}", e))?;
+    l.img_draw_test_pattern();
+    l.flush().map_err(|e| format!("flush(): {:?}", e))?;
+    thread::sleep(Duration::from_secs(secs));
+    l.lcd_clear(BLACK)
+        .map_err(|e| format!("lcd_clear(): {:?}", e))?;
+    Ok(())
+}
+
+/// The dashboard, `layout` filled in with `stats`
+fn lcd_display_stuff<B: DisplayBus>(l: &mut Lcd<B>, layout: &Layout, stats: &json::JsonValue) {
+    layout.draw(l, stats);
+
+    if let Err(e) = l.flush() {
+        error!("{}(): {:?}", func_name!(), e);
+    }
+}
+
+/// Network, storage, crypto, processes or clock page
+fn lcd_display_page<B: DisplayBus>(l: &mut Lcd<B>, pager: &mut Pager, stats: &json::JsonValue) {
+    pager.draw(l, stats);
+
+    if let Err(e) = l.flush() {
+        error!("{}(): {:?}", func_name!(), e);
+    }
+}
+
+/// Title bar with the metric and its latest value, the chart below
+fn lcd_display_chart<B: DisplayBus>(l: &mut Lcd<B>, history: &History, chart: &ChartConfig) {
+    let (img_width, img_height) = l.img_size();
+    let metric = chart.metric;
+
+    let latest = history
+        .latest()
+        .and_then(|sample| metric.value(sample))
+        .map(|value| metric.format(value))
+        .unwrap_or_else(|| String::from("--"));
+    let title = format!("{} {}", metric.label(), latest);
+    l.img_draw_rect2(0, 0, img_width, 16, WHITE);
     l.img_draw_string(
-        &((IMG_WIDTH / 2 - load.len() * FONT12.width) - 4),
-        &(162 + 24),
-        &(load),
+        &(img_width.saturating_sub(measure_text(&title, &FONT12).0) / 2),
+        &(4),
+        &title,
         &FONT12,
-        WHITE,
         BLACK,
+        WHITE,
     );
 
+    let span = format!("last {} min", history.span().as_secs().div_ceil(60));
     l.img_draw_string(
-        &(IMG_WIDTH / 2 + 6),
-        &(162),
-        "CPU Temp",
+        &(img_width.saturating_sub(measure_text(&span, &FONT8).0) / 2),
+        &(img_height - FONT8.height - 1),
+        &span,
         &FONT8,
-        BLUE2,
-        BLACK,
-    );
-    l.img_draw_string(
-        &(IMG_WIDTH / 2 + (IMG_WIDTH / 2 - temp.len() * FONT12.width) - 4),
-        &(162 + 24),
-        &(temp),
-        &FONT12,
-        WHITE,
+        GRAY,
         BLACK,
     );
 
-    l.img_draw_rect2(
-        IMG_WIDTH / 2,
-        102 + 24 + 2 + 2 + FONT12.height * 2,
-        1,
-        64,
-        ORANGE,
-    );
-
-    l.img_draw_rect2(1, 218, IMG_WIDTH - 2, FONT16.height * 2 + 2 + 2 + 2, ORANGE);
+    Chart::new(2, 20, img_width - 4, img_height - 20 - FONT8.height - 4)
+        .with_style(chart.style)
+        .with_colours(ORANGE, BLACK)
+        .draw(l, &history.series(metric), |value| metric.format(value));
 
-    if let Ok(crypto_result) = r_s1.try_recv() {
-        *btc = crypto_result.btc_cmp_str.clone();
-        crypto_result.print();
-    };
-
-    l.img_draw_string(
-        &((IMG_WIDTH - btc.len() * FONT16.width) / 2),
-        &(220 + 4),
-        btc,
-        &FONT16,
-        BLACK,
-        ORANGE,
-    );
-
-    l.img_draw_image(0, 0, LCD_WIDTH, LCD_HEIGHT);
+    if let Err(e) = l.flush() {
+        error!("{}(): {:?}", func_name!(), e);
+    }
 }
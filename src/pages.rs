@@ -0,0 +1,890 @@
+//! Pages flipped through with the joystick: the dashboard, network
+//! interfaces, mounted filesystems, bitcoin prices, the busiest
+//! processes, a big clock and the history chart.
+//!
+//! Left and right change page, up and down move through the rows of a
+//! list, press opens the highlighted row and press again closes it. With
+//! `pages.rotate_secs` set the pages also turn on their own, holding
+//! while a row is open or for a while after a key press.
+//!
+//! pages.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 20-Jul-2025
+//!
+
+use crate::bindings::Power;
+use crate::bus::*;
+use crate::config::*;
+use crate::defs::*;
+use crate::fonts::font8::*;
+use crate::fonts::font12::*;
+use crate::fonts::font16::*;
+use crate::fonts::font24::*;
+use crate::fonts::font48::*;
+use crate::layout::stat;
+use crate::lcd::lcd::*;
+use crate::stats::*;
+use crate::text::*;
+use crate::typeset::*;
+use chrono::Local;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use systemstat::{IpAddr, Platform, System};
+
+const HEADER_HEIGHT: usize = 16;
+const ROW_HEIGHT: usize = 14; // FONT12 and a pixel above and below
+const DETAIL_HEIGHT: usize = 24; // FONT8 label over a FONT12 value
+const MAX_PROCESSES: usize = 20;
+const OVERLAY_SECS: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Page {
+    Overview,
+    Network,
+    Storage,
+    Crypto,
+    Processes,
+    Clock,
+    Chart,
+}
+
+pub const DEFAULT_PAGES: [Page; 7] = [
+    Page::Overview,
+    Page::Network,
+    Page::Storage,
+    Page::Crypto,
+    Page::Processes,
+    Page::Clock,
+    Page::Chart,
+];
+
+impl Page {
+    pub fn title(self) -> &'static str {
+        match self {
+            Page::Overview => "Overview",
+            Page::Network => "Network",
+            Page::Storage => "Storage",
+            Page::Crypto => "Bitcoin",
+            Page::Processes => "Processes",
+            Page::Clock => "Clock",
+            Page::Chart => "Chart",
+        }
+    }
+
+    fn is_list(self) -> bool {
+        matches!(
+            self,
+            Page::Network | Page::Storage | Page::Crypto | Page::Processes
+        )
+    }
+}
+
+impl From<ScreenView> for Page {
+    fn from(view: ScreenView) -> Self {
+        match view {
+            ScreenView::Dashboard => Page::Overview,
+            ScreenView::Chart => Page::Chart,
+        }
+    }
+}
+
+/// Joystick moves, from keys_check() to the main loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageCmd {
+    Next,   // right
+    Prev,   // left
+    Up,     // up
+    Down,   // down
+    Select, // press
+}
+
+impl PageCmd {
+    /// The move as seen on a panel turned `degrees` clockwise from its
+    /// upright orientation, the joystick turns with the panel
+    pub fn rotated(self, degrees: u16) -> Self {
+        // Clockwise from the top
+        const COMPASS: [PageCmd; 4] = [PageCmd::Up, PageCmd::Next, PageCmd::Down, PageCmd::Prev];
+        let turns = (degrees / 90 % 4) as usize;
+        match COMPASS.iter().position(|&cmd| cmd == self) {
+            Some(i) => COMPASS[(i + 4 - turns) % 4],
+            None => self,
+        }
+    }
+}
+
+/// Shown over the page until a key is pressed, or for OVERLAY_SECS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay {
+    Confirm(Power), // joystick press to go ahead
+    IpQr,
+}
+
+/// One line of a list page, `detail` is shown when it is opened
+#[derive(Debug, Clone, Default)]
+struct Row {
+    key: String, // follows the row when the list is re-sorted
+    label: String,
+    value: String,
+    detail: Vec<(String, String)>,
+}
+
+/// Which page is up and where in it, plus the counters the network and
+/// process pages turn into rates between frames
+pub struct Pager {
+    pages: Vec<Page>,
+    current: usize,
+    cursor: usize,
+    scroll: usize,
+    open: Option<String>, // key of the open row
+    opening: bool,        // open the row under the cursor on the next frame
+    detail_scroll: usize,
+    rotate: Option<Duration>,
+    shown_at: Instant,
+    overlay: Option<(Overlay, Instant)>,
+    changed: bool,
+    sys: System,
+    net: HashMap<String, (Instant, u64, u64)>,
+    procs: HashMap<u32, u64>,
+    procs_at: Option<Instant>,
+    fonts: PropFonts,
+}
+
+// Proportional fonts for the clock and the overlays, kept between frames
+// so kerning is worked out once per character
+struct PropFonts {
+    large: Proportional<&'static dyn Font>,
+    medium: Proportional<&'static dyn Font>,
+    small: Proportional<&'static dyn Font>,
+}
+
+impl PropFonts {
+    fn new() -> Self {
+        Self {
+            large: Proportional::new(&FONT48 as &dyn Font).with_spacing(3),
+            medium: Proportional::new(&FONT24 as &dyn Font).with_spacing(2),
+            small: Proportional::new(&FONT16 as &dyn Font),
+        }
+    }
+}
+
+impl Pager {
+    pub fn new(config: &PagesConfig) -> Self {
+        Self {
+            pages: config.list.clone(),
+            current: 0,
+            cursor: 0,
+            scroll: 0,
+            open: None,
+            opening: false,
+            detail_scroll: 0,
+            rotate: config.rotation(),
+            shown_at: Instant::now(),
+            overlay: None,
+            changed: true,
+            sys: System::new(),
+            net: HashMap::new(),
+            procs: HashMap::new(),
+            procs_at: None,
+            fonts: PropFonts::new(),
+        }
+    }
+
+    /// New page list and timer, staying on the same page if it is still
+    /// in the list
+    pub fn reconfigure(&mut self, config: &PagesConfig) {
+        let page = self.page();
+        self.pages = config.list.clone();
+        self.rotate = config.rotation();
+        if self.page() != page {
+            self.show(page);
+        }
+    }
+
+    pub fn page(&self) -> Page {
+        self.pages
+            .get(self.current)
+            .copied()
+            .unwrap_or(Page::Overview)
+    }
+
+    /// Go to `page`, if it is in the list
+    pub fn show(&mut self, page: Page) {
+        if let Some(i) = self.pages.iter().position(|&p| p == page) {
+            self.turn_to(i);
+        }
+    }
+
+    pub fn show_overlay(&mut self, overlay: Overlay) {
+        self.overlay = Some((overlay, Instant::now()));
+        self.changed = true;
+    }
+
+    pub fn has_overlay(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// Move, or close the overlay. Returns the power action if this
+    /// confirmed one.
+    pub fn handle(&mut self, cmd: PageCmd) -> Option<Power> {
+        debug!("{}(): {:?} on {:?}", func_name!(), cmd, self.page());
+        self.shown_at = Instant::now();
+        if let Some((overlay, _)) = self.overlay.take() {
+            self.changed = true;
+            return match overlay {
+                Overlay::Confirm(power) if cmd == PageCmd::Select => Some(power),
+                _ => None,
+            };
+        }
+        let len = self.pages.len().max(1);
+        match cmd {
+            PageCmd::Next => self.turn_to((self.current + 1) % len),
+            PageCmd::Prev => self.turn_to((self.current + len - 1) % len),
+            PageCmd::Up if self.open.is_some() => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(1)
+            }
+            PageCmd::Down if self.open.is_some() => self.detail_scroll += 1,
+            PageCmd::Up => self.cursor = self.cursor.saturating_sub(1),
+            PageCmd::Down => self.cursor += 1, // stopped at the last row on the next frame
+            PageCmd::Select if self.open.is_some() => {
+                self.open = None;
+                self.changed = true;
+            }
+            PageCmd::Select => {
+                // The row isn't known until the list is read
+                self.opening = self.page().is_list();
+                self.detail_scroll = 0;
+                self.changed = true;
+            }
+        }
+        None
+    }
+
+    /// Close an overlay that has been up too long, turn the page if it
+    /// has been up long enough
+    pub fn tick(&mut self) {
+        if let Some((_, since)) = self.overlay {
+            if since.elapsed() >= Duration::from_secs(OVERLAY_SECS) {
+                self.overlay = None;
+                self.changed = true;
+                self.shown_at = Instant::now();
+            }
+        } else if self.until_rotate() == Some(Duration::ZERO) {
+            self.turn_to((self.current + 1) % self.pages.len().max(1));
+        }
+    }
+
+    /// Time until tick() has something to do, None if nothing is timed
+    pub fn until_tick(&self) -> Option<Duration> {
+        match self.overlay {
+            Some((_, since)) => {
+                Some(Duration::from_secs(OVERLAY_SECS).saturating_sub(since.elapsed()))
+            }
+            None => self.until_rotate(),
+        }
+    }
+
+    // Time left on this page, None if the pages don't turn on their own
+    fn until_rotate(&self) -> Option<Duration> {
+        if self.open.is_some() || self.pages.len() < 2 {
+            return None;
+        }
+        self.rotate
+            .map(|rotate| rotate.saturating_sub(self.shown_at.elapsed()))
+    }
+
+    /// True once after the page changed, time to clear the screen
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    fn turn_to(&mut self, i: usize) {
+        if i != self.current {
+            self.changed = true;
+        }
+        self.current = i;
+        self.cursor = 0;
+        self.scroll = 0;
+        self.opening = false;
+        if self.open.take().is_some() {
+            self.changed = true;
+        }
+        self.shown_at = Instant::now();
+    }
+
+    /// The overlay, or the current page if it is one of the pages drawn
+    /// here. The dashboard and the chart are drawn by their own code.
+    pub fn draw<B: DisplayBus>(&mut self, l: &mut Lcd<B>, stats: &json::JsonValue) {
+        match self.overlay {
+            Some((Overlay::Confirm(power), _)) => return draw_confirm(l, power, &self.fonts.small),
+            Some((Overlay::IpQr, _)) => return draw_ip_qr(l, &stat(stats, _J_IP_ADDRESS)),
+            None => {}
+        }
+        let page = self.page();
+        let rows = match page {
+            Page::Network => self.network_rows(),
+            Page::Storage => self.storage_rows(),
+            Page::Crypto => crypto_rows(stats),
+            Page::Processes => self.process_rows(),
+            Page::Clock => return self.draw_clock(l),
+            Page::Overview | Page::Chart => return,
+        };
+
+        // Hold on to the highlighted row's key so it stays open while
+        // the list moves under it
+        self.cursor = self.cursor.min(rows.len().saturating_sub(1));
+        if std::mem::take(&mut self.opening) {
+            self.open = rows
+                .get(self.cursor)
+                .filter(|row| !row.detail.is_empty())
+                .map(|row| row.key.clone());
+        }
+        match &self.open {
+            Some(key) => {
+                let row = rows.iter().find(|row| &row.key == key);
+                self.draw_detail(l, row);
+            }
+            None => self.draw_list(l, page.title(), &rows),
+        }
+    }
+
+    fn draw_header<B: DisplayBus>(&self, l: &mut Lcd<B>, title: &str) {
+        let (w, _) = l.img_size();
+        let position = format!("{}/{}", self.current + 1, self.pages.len());
+        let (pw, _) = measure_text(&position, &FONT8);
+        let title_box = (4, 2, w - pw - 12, FONT12.height);
+        let position_box = (w - pw - 8, 4, pw + 4, FONT8.height);
+        l.img_draw_rect_around((0, 0, w, HEADER_HEIGHT), &[title_box, position_box], WHITE);
+        let (x, y, tw, _) = title_box;
+        l.img_draw_text_aligned(
+            x as i32,
+            y as i32,
+            tw,
+            title,
+            &FONT12,
+            BLACK,
+            Some(WHITE),
+            Align::Left,
+        );
+        let (x, y, pw, _) = position_box;
+        l.img_draw_text_aligned(
+            x as i32,
+            y as i32,
+            pw,
+            &position,
+            &FONT8,
+            GRAY,
+            Some(WHITE),
+            Align::Right,
+        );
+    }
+
+    fn draw_list<B: DisplayBus>(&mut self, l: &mut Lcd<B>, title: &str, rows: &[Row]) {
+        let (w, h) = l.img_size();
+        self.draw_header(l, title);
+        let top = HEADER_HEIGHT + 2;
+        let body = (0, HEADER_HEIGHT, w, h - HEADER_HEIGHT);
+        if rows.is_empty() {
+            let y = top + (h - top) / 2 - FONT12.height / 2;
+            l.img_draw_rect_around(body, &[(0, y, w, FONT12.height)], BLACK);
+            let text = "Nothing here";
+            l.img_draw_text_aligned(
+                0,
+                y as i32,
+                w,
+                text,
+                &FONT12,
+                GRAY,
+                Some(BLACK),
+                Align::Centre,
+            );
+            return;
+        }
+
+        // Keep the cursor in view
+        let visible = ((h - top) / ROW_HEIGHT).max(1);
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + visible {
+            self.scroll = self.cursor + 1 - visible;
+        }
+        self.scroll = self.scroll.min(rows.len().saturating_sub(visible));
+
+        let scrollbar = rows.len() > visible;
+        let width = if scrollbar { w - 4 } else { w };
+        let selectable = rows.iter().any(|row| !row.detail.is_empty());
+        let shown = rows.len().saturating_sub(self.scroll).min(visible);
+        let mut drawn = vec![(0, top, width, shown * ROW_HEIGHT)];
+        if scrollbar {
+            drawn.push((w - 2, top, 2, h - top));
+        }
+        l.img_draw_rect_around(body, &drawn, BLACK);
+        for (n, row) in rows.iter().skip(self.scroll).take(visible).enumerate() {
+            let y = top + n * ROW_HEIGHT;
+            let highlight = selectable && self.scroll + n == self.cursor;
+            let (fg, bg) = if highlight {
+                (BLACK, ORANGE)
+            } else {
+                (WHITE, BLACK)
+            };
+            // The value gets what the label leaves, at least half the row
+            let (lw, _) = measure_text(&row.label, &FONT12);
+            let max = (width - 12).saturating_sub(lw).max((width - 8) / 2);
+            let value = ellipsize(&row.value, &FONT12, max);
+            let (vw, _) = measure_text(&value, &FONT12);
+            let lw = width.saturating_sub(vw + 12);
+            let vw = (width - 8).saturating_sub(lw);
+            let boxes = [
+                (4, y + 1, lw, FONT12.height),
+                (4 + lw, y + 1, vw, FONT12.height),
+            ];
+            l.img_draw_rect_around((0, y, width, ROW_HEIGHT), &boxes, bg);
+            let y = (y + 1) as i32;
+            l.img_draw_text_aligned(4, y, lw, &row.label, &FONT12, fg, Some(bg), Align::Left);
+            let vc = if highlight { BLACK } else { BLUE2 };
+            let x = 4 + lw as i32;
+            l.img_draw_text_aligned(x, y, vw, &value, &FONT12, vc, Some(bg), Align::Right);
+        }
+
+        if scrollbar {
+            let track = h - top;
+            let thumb = (track * visible / rows.len()).max(4);
+            let y = top + (track - thumb) * self.scroll / (rows.len() - visible);
+            l.img_draw_rect(w - 2, top, 2, y - top, GRAY);
+            l.img_draw_rect(w - 2, y, 2, thumb, WHITE);
+            l.img_draw_rect(w - 2, y + thumb, 2, top + track - y - thumb, GRAY);
+        }
+    }
+
+    fn draw_detail<B: DisplayBus>(&mut self, l: &mut Lcd<B>, row: Option<&Row>) {
+        let (w, h) = l.img_size();
+        let top = HEADER_HEIGHT + 2;
+        let body = (0, HEADER_HEIGHT, w, h - HEADER_HEIGHT);
+        let Some(row) = row else {
+            self.draw_header(l, "Gone");
+            let y = top + (h - top) / 2 - FONT12.height / 2;
+            l.img_draw_rect_around(body, &[(0, y, w, FONT12.height)], BLACK);
+            l.img_draw_text_aligned(
+                0,
+                y as i32,
+                w,
+                "No longer there",
+                &FONT12,
+                GRAY,
+                Some(BLACK),
+                Align::Centre,
+            );
+            return;
+        };
+        self.draw_header(l, &row.label);
+
+        let visible = ((h - top) / DETAIL_HEIGHT).max(1);
+        self.detail_scroll = self
+            .detail_scroll
+            .min(row.detail.len().saturating_sub(visible));
+        let details: Vec<_> = row
+            .detail
+            .iter()
+            .skip(self.detail_scroll)
+            .take(visible)
+            .collect();
+        // Label and value boxes of each
+        let boxes: Vec<_> = (0..details.len())
+            .flat_map(|n| {
+                let y = top + n * DETAIL_HEIGHT;
+                [
+                    (4, y, w - 8, FONT8.height),
+                    (4, y + FONT8.height + 2, w - 8, FONT12.height),
+                ]
+            })
+            .collect();
+        l.img_draw_rect_around(body, &boxes, BLACK);
+        for (n, (label, value)) in details.into_iter().enumerate() {
+            let y = (top + n * DETAIL_HEIGHT) as i32;
+            l.img_draw_text_aligned(4, y, w - 8, label, &FONT8, BLUE2, Some(BLACK), Align::Left);
+            let y = y + FONT8.height as i32 + 2;
+            l.img_draw_text_aligned(
+                4,
+                y,
+                w - 8,
+                value,
+                &FONT12,
+                WHITE,
+                Some(BLACK),
+                Align::Right,
+            );
+        }
+    }
+
+    // Hours and minutes as large as they fit, the date under them
+    fn draw_clock<B: DisplayBus>(&self, l: &mut Lcd<B>) {
+        let (w, h) = l.img_size();
+        let now = Local::now();
+        let time = now.format("%H:%M").to_string();
+        let date = now.format("%a %-d %b %Y").to_string();
+
+        let fonts = &self.fonts;
+        let font: &dyn Font = if measure_text(&time, &fonts.large).0 + 8 <= w {
+            &fonts.large
+        } else {
+            &fonts.medium
+        };
+        let block = font.height() + 6 + FONT16.height;
+        let y = h.saturating_sub(block) / 2;
+        let date_y = y + font.height() + 6;
+
+        let boxes = [(0, y, w, font.height()), (0, date_y, w, FONT16.height)];
+        l.img_draw_rect_around((0, 0, w, h), &boxes, BLACK);
+        l.img_draw_text_aligned(
+            0,
+            y as i32,
+            w,
+            &time,
+            font,
+            WHITE,
+            Some(BLACK),
+            Align::Centre,
+        );
+        let y = date_y as i32;
+        l.img_draw_text_aligned(
+            0,
+            y,
+            w,
+            &date,
+            &fonts.small,
+            ORANGE,
+            Some(BLACK),
+            Align::Centre,
+        );
+    }
+
+    // Interfaces but loopback, first IPv4 address as the value
+    fn network_rows(&mut self) -> Vec<Row> {
+        let networks = match self.sys.networks() {
+            Ok(networks) => networks,
+            Err(e) => {
+                error!("{}(): Error reading interfaces: {}", func_name!(), e);
+                return Vec::new();
+            }
+        };
+        let now = Instant::now();
+        let mut rows = Vec::new();
+        for (name, network) in networks.iter().filter(|(name, _)| name.as_str() != "lo") {
+            let addrs: Vec<String> = network
+                .addrs
+                .iter()
+                .filter_map(|a| match a.addr {
+                    IpAddr::V4(ip) => Some(ip.to_string()),
+                    IpAddr::V6(ip) => Some(ip.to_string()),
+                    _ => None,
+                })
+                .collect();
+            let ipv4 = network.addrs.iter().find_map(|a| match a.addr {
+                IpAddr::V4(ip) => Some(ip.to_string()),
+                _ => None,
+            });
+
+            let mut detail: Vec<(String, String)> = addrs
+                .iter()
+                .map(|addr| ("Address".to_string(), addr.clone()))
+                .collect();
+            if let Ok(mac) = std::fs::read_to_string(format!("/sys/class/net/{}/address", name)) {
+                detail.push(("MAC".to_string(), mac.trim().to_string()));
+            }
+            if let Ok(stats) = self.sys.network_stats(name) {
+                let (rx, tx) = (stats.rx_bytes.as_u64(), stats.tx_bytes.as_u64());
+                if let Some((then, last_rx, last_tx)) = self.net.get(name) {
+                    let secs = now.duration_since(*then).as_secs_f32().max(0.001);
+                    let rate = |bytes: u64, last: u64| {
+                        format!(
+                            "{}/s",
+                            format_bytes(bytes.saturating_sub(last) as f32 / secs)
+                        )
+                    };
+                    detail.push(("In".to_string(), rate(rx, *last_rx)));
+                    detail.push(("Out".to_string(), rate(tx, *last_tx)));
+                }
+                detail.push(("Received".to_string(), format_bytes(rx as f32)));
+                detail.push(("Sent".to_string(), format_bytes(tx as f32)));
+                detail.push((
+                    "Errors in / out".to_string(),
+                    format!("{} / {}", stats.rx_errors, stats.tx_errors),
+                ));
+                self.net.insert(name.clone(), (now, rx, tx));
+            }
+
+            rows.push(Row {
+                key: name.clone(),
+                label: name.clone(),
+                value: ipv4.unwrap_or_else(|| "-".to_string()),
+                detail,
+            });
+        }
+        rows
+    }
+
+    // Real filesystems, most used first
+    fn storage_rows(&self) -> Vec<Row> {
+        let mounts = match self.sys.mounts() {
+            Ok(mounts) => mounts,
+            Err(e) => {
+                error!("{}(): Error reading mounts: {}", func_name!(), e);
+                return Vec::new();
+            }
+        };
+        let mut mounts: Vec<_> = mounts
+            .into_iter()
+            .filter(|fs| fs.total.as_u64() > 0 && fs.fs_mounted_from.starts_with('/'))
+            .map(|fs| {
+                let total = fs.total.as_u64();
+                let used = total.saturating_sub(fs.free.as_u64());
+                (used as f32 * 100.0 / total as f32, used, fs)
+            })
+            .collect();
+        mounts.sort_by(|a, b| b.0.total_cmp(&a.0));
+        mounts
+            .into_iter()
+            .map(|(percent, used, fs)| Row {
+                key: fs.fs_mounted_on.clone(),
+                label: fs.fs_mounted_on.clone(),
+                value: format!("{:.0}%", percent),
+                detail: vec![
+                    ("Device".to_string(), fs.fs_mounted_from.clone()),
+                    ("Type".to_string(), fs.fs_type.clone()),
+                    ("Size".to_string(), format_bytes(fs.total.as_u64() as f32)),
+                    ("Used".to_string(), format_bytes(used as f32)),
+                    (
+                        "Available".to_string(),
+                        format_bytes(fs.avail.as_u64() as f32),
+                    ),
+                    (
+                        "Files".to_string(),
+                        format!("{} of {}", fs.files, fs.files_total),
+                    ),
+                ],
+            })
+            .collect()
+    }
+
+    // Busiest first by CPU since the last frame, by memory on the first
+    fn process_rows(&mut self) -> Vec<Row> {
+        let procs = read_procs();
+        let now = Instant::now();
+        let secs = self
+            .procs_at
+            .map(|then| now.duration_since(then).as_secs_f32());
+        let cpu = |p: &Proc| -> Option<f32> {
+            let secs = secs.filter(|&secs| secs > 0.0)?;
+            let last = self.procs.get(&p.pid)?;
+            Some(p.ticks.saturating_sub(*last) as f32 * 100.0 / (secs * clock_ticks()))
+        };
+
+        let mut procs: Vec<(Option<f32>, Proc)> = procs.into_iter().map(|p| (cpu(&p), p)).collect();
+        self.procs = procs.iter().map(|(_, p)| (p.pid, p.ticks)).collect();
+        self.procs_at = Some(now);
+
+        procs.sort_by(|(a_cpu, a), (b_cpu, b)| {
+            b_cpu
+                .unwrap_or(0.0)
+                .total_cmp(&a_cpu.unwrap_or(0.0))
+                .then(b.rss.cmp(&a.rss))
+        });
+        procs
+            .into_iter()
+            .take(MAX_PROCESSES)
+            .map(|(cpu, p)| {
+                let cpu = cpu.map_or_else(|| "--".to_string(), |cpu| format!("{:.1}%", cpu));
+                Row {
+                    key: p.pid.to_string(),
+                    label: p.name.clone(),
+                    value: cpu.clone(),
+                    detail: vec![
+                        ("PID".to_string(), p.pid.to_string()),
+                        ("State".to_string(), p.state.to_string()),
+                        ("CPU".to_string(), cpu),
+                        ("Memory".to_string(), format_bytes(p.rss as f32)),
+                        ("Threads".to_string(), p.threads.to_string()),
+                        ("Command".to_string(), p.command),
+                    ],
+                }
+            })
+            .collect()
+    }
+}
+
+// Red title, what the press will do and how to back out
+fn draw_confirm<B: DisplayBus>(l: &mut Lcd<B>, power: Power, font: &dyn Font) {
+    let (w, h) = l.img_size();
+    let verb = power.verb();
+    let mut title = format!("{}?", verb);
+    title[..1].make_ascii_uppercase();
+    let text = format!("Press the joystick to {}, move it to cancel.", verb);
+    let top = HEADER_HEIGHT + 8;
+    let title_box = (4, 2, w - 8, FONT12.height);
+    let text_box = (4, top, w - 8, h - top);
+    l.img_draw_rect_around((0, 0, w, HEADER_HEIGHT), &[title_box], RED);
+    l.img_draw_rect_around((0, HEADER_HEIGHT, w, h - HEADER_HEIGHT), &[text_box], BLACK);
+    l.img_draw_text_aligned(4, 2, w - 8, &title, &FONT12, WHITE, Some(RED), Align::Left);
+    l.img_draw_text_wrapped(
+        4,
+        top as i32,
+        w - 8,
+        h - top,
+        &text,
+        font,
+        WHITE,
+        Some(BLACK),
+        Align::Centre,
+    );
+}
+
+// The address as a QR code on white, as large as fits, with the address
+// under it
+fn draw_ip_qr<B: DisplayBus>(l: &mut Lcd<B>, ip: &str) {
+    let (w, h) = l.img_size();
+    let code = match qrcode::QrCode::new(ip.as_bytes()) {
+        Ok(code) => code,
+        Err(e) => {
+            error!("{}(): {}: {:?}", func_name!(), ip, e);
+            l.img_draw_rect(0, 0, w, h, WHITE);
+            return;
+        }
+    };
+    let modules = code.width();
+    let text_height = FONT12.height + 4;
+    // Four modules of quiet zone all round
+    let scale = (w.min(h - text_height) / (modules + 8)).max(1);
+    let size = modules * scale;
+    let (x0, y0) = ((w - size) / 2, (h - text_height).saturating_sub(size) / 2);
+    let y = h - text_height + 1;
+    let boxes = [(x0, y0, size, size), (0, y, w, FONT12.height)];
+    l.img_draw_rect_around((0, 0, w, h), &boxes, WHITE);
+    for (i, colour) in code.to_colors().iter().enumerate() {
+        let (x, y) = (x0 + i % modules * scale, y0 + i / modules * scale);
+        let colour = if *colour == qrcode::Color::Dark {
+            BLACK
+        } else {
+            WHITE
+        };
+        l.img_draw_rect(x, y, scale, scale, colour);
+    }
+    l.img_draw_text_aligned(
+        0,
+        y as i32,
+        w,
+        ip,
+        &FONT12,
+        BLACK,
+        Some(WHITE),
+        Align::Centre,
+    );
+}
+
+// Prices as the dashboard shows them
+fn crypto_rows(stats: &json::JsonValue) -> Vec<Row> {
+    [
+        ("Price", _J_BTC_CMP_STR),
+        ("High", _J_BTC_ATH_STR),
+        ("To high", _J_BTC_CMP_ATH_DIFF_STR),
+    ]
+    .into_iter()
+    .map(|(label, key)| Row {
+        key: key.to_string(),
+        label: label.to_string(),
+        value: stats[key].as_str().unwrap_or("-").to_string(),
+        detail: Vec::new(),
+    })
+    .collect()
+}
+
+/// A process from /proc/<pid>/stat
+#[derive(Debug, Clone)]
+struct Proc {
+    pid: u32,
+    name: String,
+    command: String,
+    state: char,
+    ticks: u64, // user and system time, in clock ticks
+    threads: u64,
+    rss: u64, // bytes
+}
+
+fn read_procs() -> Vec<Proc> {
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    dir.flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            let mut proc = parse_stat(pid, &stat)?;
+            // Kernel threads have no command line
+            if let Ok(cmdline) = std::fs::read(format!("/proc/{}/cmdline", pid)) {
+                proc.command = String::from_utf8_lossy(&cmdline)
+                    .split('\0')
+                    .filter(|arg| !arg.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+            }
+            if proc.command.is_empty() {
+                proc.command = format!("[{}]", proc.name);
+            }
+            Some(proc)
+        })
+        .collect()
+}
+
+// "pid (name) state ppid ...", the name can hold spaces and brackets
+fn parse_stat(pid: u32, stat: &str) -> Option<Proc> {
+    let (open, close) = (stat.find('(')?, stat.rfind(')')?);
+    let name = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+    // Fields from the state on, see proc_pid_stat(5)
+    let field = |n: usize| fields.get(n - 3).and_then(|f| f.parse::<u64>().ok());
+    Some(Proc {
+        pid,
+        name,
+        command: String::new(),
+        state: fields.first()?.chars().next()?,
+        ticks: field(14)? + field(15)?,
+        threads: field(20)?,
+        rss: field(24)? * page_size(),
+    })
+}
+
+fn clock_ticks() -> f32 {
+    // SAFETY: sysconf() only reads a system constant
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f32,
+        _ => 100.0,
+    }
+}
+
+fn page_size() -> u64 {
+    // SAFETY: as above
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+/// "512", "1.2k", "3.4M", "5.6G"
+fn format_bytes(bytes: f32) -> String {
+    const UNITS: [&str; 4] = ["k", "M", "G", "T"];
+    let mut value = bytes;
+    let mut unit = "";
+    for u in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = u;
+    }
+    if unit.is_empty() {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}{}", value, unit)
+    }
+}
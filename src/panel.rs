@@ -0,0 +1,456 @@
+//! Panel controller profiles: resolution, window offsets, MADCTL bits,
+//! colour order and init/gamma sequences for each supported LCD HAT.
+//!
+//! panel.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 06-Jul-2025
+//!
+
+use crate::defs::*;
+use crate::lcd::lcd::*;
+use CmdOrData::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Controller {
+    St7735s,
+    St7789,
+    Ili9341,
+}
+
+/// Colour order of the glass, BGR panels need the MADCTL BGR bit set
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColourOrder {
+    Rgb,
+    Bgr,
+}
+
+pub struct PanelProfile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub controller: Controller,
+    pub width: usize, // at Rotate0
+    pub height: usize,
+    pub gram_width: usize, // controller memory, the glass may only use part of it
+    pub gram_height: usize,
+    pub offsets: [(u16, u16); 4], // window (x, y) offset for Rotate0, 90, 180, 270
+    pub madctl: [UBYTE; 4],       // MY/MX/MV bits for Rotate0, 90, 180, 270
+    pub colour_order: ColourOrder,
+    pub inverted: bool, // IPS glass, shows true colours with inversion on
+    pub orientation: LcdOrientation, // upright as mounted on the HAT
+    pub init_seq: &'static [CmdOrData],
+    pub post_init_seq: &'static [CmdOrData], // after init_seq, before the gamma
+    pub gamma_seq: &'static [CmdOrData],
+}
+
+impl PanelProfile {
+    pub fn madctl_value(&self, orientation: LcdOrientation) -> UBYTE {
+        let colour = match self.colour_order {
+            ColourOrder::Rgb => 0x00,
+            ColourOrder::Bgr => 0x08,
+        };
+        self.madctl[orientation.index()] | colour
+    }
+
+    pub fn offset(&self, orientation: LcdOrientation) -> (u16, u16) {
+        self.offsets[orientation.index()]
+    }
+}
+
+/// Waveshare 1.44" LCD HAT, 128x128
+pub const WAVESHARE_144: PanelProfile = PanelProfile {
+    name: "waveshare-1.44",
+    description: "Waveshare 1.44\" LCD HAT, ST7735S 128x128",
+    controller: Controller::St7735s,
+    width: 128,
+    height: 128,
+    gram_width: 128,
+    gram_height: 128,
+    offsets: [(0, 0); 4],
+    madctl: [0x00, 0x60, 0xC0, 0xA0],
+    colour_order: ColourOrder::Bgr,
+    inverted: false,
+    orientation: LcdOrientation::Rotate90,
+    init_seq: &ST7735S_INIT_SEQ,
+    post_init_seq: &ST7735S_COLUMN_INVERSION_SEQ,
+    gamma_seq: &ILI9341_GAMMA_SEQ,
+};
+
+/// Same HAT with Waveshare's own init sequence, try this if colours or
+/// contrast look off with the default one.
+pub const WAVESHARE_144_VENDOR: PanelProfile = PanelProfile {
+    name: "waveshare-1.44-vendor",
+    description: "Waveshare 1.44\" LCD HAT, ST7735S 128x128, vendor init",
+    init_seq: &ST7735S_VENDOR_INIT_SEQ,
+    post_init_seq: &[],
+    gamma_seq: &[],
+    ..WAVESHARE_144
+};
+
+/// Waveshare 1.3" LCD HAT, 240x240 glass on a 240x320 controller, so
+/// the rotations that count rows or columns from the far end need 80
+/// pixels of offset.
+pub const WAVESHARE_13: PanelProfile = PanelProfile {
+    name: "waveshare-1.3",
+    description: "Waveshare 1.3\" LCD HAT, ST7789 240x240",
+    controller: Controller::St7789,
+    width: 240,
+    height: 240,
+    gram_width: 240,
+    gram_height: 320,
+    offsets: [(0, 0), (80, 0), (0, 80), (0, 0)],
+    madctl: [0x00, 0x60, 0xC0, 0xA0],
+    colour_order: ColourOrder::Rgb,
+    inverted: true,
+    orientation: LcdOrientation::Rotate90,
+    init_seq: &ST7789_INIT_SEQ,
+    post_init_seq: &[],
+    gamma_seq: &ST7789_GAMMA_SEQ,
+};
+
+/// 2.4"/2.8" ILI9341 HATs, 240x320 portrait
+pub const ILI9341_240X320: PanelProfile = PanelProfile {
+    name: "ili9341",
+    description: "2.4\"/2.8\" LCD HAT, ILI9341 240x320",
+    controller: Controller::Ili9341,
+    width: 240,
+    height: 320,
+    gram_width: 240,
+    gram_height: 320,
+    offsets: [(0, 0); 4],
+    madctl: [0x40, 0x20, 0x80, 0xE0],
+    colour_order: ColourOrder::Bgr,
+    inverted: false,
+    orientation: LcdOrientation::Rotate0,
+    init_seq: &ILI9341_INIT_SEQ,
+    post_init_seq: &[],
+    gamma_seq: &ILI9341_GAMMA_SEQ,
+};
+
+pub const PANEL_PROFILES: [&PanelProfile; 4] = [
+    &WAVESHARE_144,
+    &WAVESHARE_144_VENDOR,
+    &WAVESHARE_13,
+    &ILI9341_240X320,
+];
+
+/// Look up a built-in profile by name, case insensitive
+pub fn panel_profile(name: &str) -> Option<&'static PanelProfile> {
+    PANEL_PROFILES
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+pub fn panel_names() -> Vec<&'static str> {
+    PANEL_PROFILES.iter().map(|p| p.name).collect()
+}
+
+/// What runs on the 1.44" HAT today, the ILI9341 style sequence
+pub const ST7735S_INIT_SEQ: [CmdOrData; 47] = [
+    Cmd(SLEEP_OUT),
+    Cmd(POWER_CONTROL_B),
+    Data(0x00),
+    Data(0xC1),
+    Data(0x30),
+    Cmd(POWER_ON_SEQ_CONTROL),
+    Data(0x64),
+    Data(0x03),
+    Data(0x12),
+    Data(0x81),
+    Cmd(DRIVER_TIMING_CONTROL_A),
+    Data(0x85),
+    Data(0x00),
+    Data(0x79),
+    Cmd(POWER_CONTROL_A),
+    Data(0x39),
+    Data(0x2C),
+    Data(0x00),
+    Data(0x34),
+    Data(0x02),
+    Cmd(PUMP_RATIO_CONTROL),
+    Data(0x20),
+    Cmd(DRIVER_TIMING_CONTROL_B),
+    Data(0x00),
+    Data(0x00),
+    Cmd(POWER_CONTROL_1),
+    Data(0x1D),
+    Cmd(POWER_CONTROL_2),
+    Data(0x12),
+    Cmd(VCOM_CONTROL_1),
+    Data(0x33),
+    Data(0x3F),
+    Cmd(VCOM_CONTROL_2),
+    Data(0x92),
+    Cmd(PIXEL_FORMAT_SET),
+    Data(0x05), // 0x03 or 0x05 or 0x06?
+    Cmd(MEMORY_ACCESS_CONTROL),
+    Data(0x08),
+    Cmd(FRAME_CONTROL_NORMAL_MODE),
+    Data(0x00),
+    Data(0x12),
+    Cmd(DISPLAY_FUNCTION_CONTROL),
+    Data(0x0A),
+    Data(0xA2),
+    Cmd(SET_TEAR_SCANLINE),
+    Data(0x00),
+    Cmd(DISPLAY_ON),
+];
+
+/// Sent after ST7735S_INIT_SEQ, as it always has been
+pub const ST7735S_COLUMN_INVERSION_SEQ: [CmdOrData; 2] = [
+    Cmd(0xB4), // Column inversion
+    Data(0x07),
+];
+
+pub const ILI9341_INIT_SEQ: [CmdOrData; 48] = [
+    Cmd(SLEEP_OUT),
+    Delay(120),
+    Cmd(POWER_CONTROL_B),
+    Data(0x00),
+    Data(0xC1),
+    Data(0x30),
+    Cmd(POWER_ON_SEQ_CONTROL),
+    Data(0x64),
+    Data(0x03),
+    Data(0x12),
+    Data(0x81),
+    Cmd(DRIVER_TIMING_CONTROL_A),
+    Data(0x85),
+    Data(0x00),
+    Data(0x79),
+    Cmd(POWER_CONTROL_A),
+    Data(0x39),
+    Data(0x2C),
+    Data(0x00),
+    Data(0x34),
+    Data(0x02),
+    Cmd(PUMP_RATIO_CONTROL),
+    Data(0x20),
+    Cmd(DRIVER_TIMING_CONTROL_B),
+    Data(0x00),
+    Data(0x00),
+    Cmd(POWER_CONTROL_1),
+    Data(0x1D),
+    Cmd(POWER_CONTROL_2),
+    Data(0x12),
+    Cmd(VCOM_CONTROL_1),
+    Data(0x33),
+    Data(0x3F),
+    Cmd(VCOM_CONTROL_2),
+    Data(0x92),
+    Cmd(PIXEL_FORMAT_SET),
+    Data(0x05), // 0x03 or 0x05 or 0x06?
+    Cmd(MEMORY_ACCESS_CONTROL),
+    Data(0x08),
+    Cmd(FRAME_CONTROL_NORMAL_MODE),
+    Data(0x00),
+    Data(0x12),
+    Cmd(DISPLAY_FUNCTION_CONTROL),
+    Data(0x0A),
+    Data(0xA2),
+    Cmd(SET_TEAR_SCANLINE),
+    Data(0x00),
+    Cmd(DISPLAY_ON),
+];
+
+pub const ILI9341_GAMMA_SEQ: [CmdOrData; 36] = [
+    Cmd(ENABLE_3G),
+    Data(0x00),
+    Cmd(GAMMA_SET),
+    Data(0x01),
+    Cmd(POSITIVE_GAMMA_CORRECTION),
+    Data(0x0F),
+    Data(0x22),
+    Data(0x1C),
+    Data(0x1B),
+    Data(0x08),
+    Data(0x0F),
+    Data(0x48),
+    Data(0xB8),
+    Data(0x34),
+    Data(0x05),
+    Data(0x0C),
+    Data(0x09),
+    Data(0x0F),
+    Data(0x07),
+    Data(0x00),
+    Cmd(NEGATIVE_GAMMA_CORRECTION),
+    Data(0x00),
+    Data(0x23),
+    Data(0x24),
+    Data(0x07),
+    Data(0x10),
+    Data(0x07),
+    Data(0x38),
+    Data(0x47),
+    Data(0x4B),
+    Data(0x0A),
+    Data(0x13),
+    Data(0x06),
+    Data(0x30),
+    Data(0x38),
+    Data(0x0F),
+];
+
+/// Waveshare's own ST7735S initialisation from their 1.44" demo code
+pub const ST7735S_VENDOR_INIT_SEQ: [CmdOrData; 77] = [
+    Cmd(0xB1),
+    Data(0x01),
+    Data(0x2C),
+    Data(0x2D),
+    //
+    Cmd(0xB2),
+    Data(0x01),
+    Data(0x2C),
+    Data(0x2D),
+    //
+    Cmd(0xB3),
+    Data(0x01),
+    Data(0x2C),
+    Data(0x2D),
+    Data(0x01),
+    Data(0x2C),
+    Data(0x2D),
+    //
+    Cmd(0xB4), // Column inversion
+    Data(0x07),
+    //
+    Cmd(0xC0), // ST7735R Power Sequence
+    Data(0xA2),
+    Data(0x02),
+    Data(0x84),
+    //
+    Cmd(0xC1),
+    Data(0xC5),
+    //
+    Cmd(0xC2),
+    Data(0x0A),
+    Data(0x00),
+    //
+    Cmd(0xC3),
+    Data(0x8A),
+    Data(0x2A),
+    //
+    Cmd(0xC4),
+    Data(0x8A),
+    Data(0xEE),
+    //
+    Cmd(0xC5), // VCOM
+    Data(0x0E),
+    //
+    Cmd(0xE0), // ST7735R Gamma Sequence
+    Data(0x0F),
+    Data(0x1A),
+    Data(0x0F),
+    Data(0x18),
+    Data(0x2F),
+    Data(0x28),
+    Data(0x20),
+    Data(0x22),
+    Data(0x1F),
+    Data(0x1B),
+    Data(0x23),
+    Data(0x37),
+    Data(0x00),
+    Data(0x07),
+    Data(0x02),
+    Data(0x10),
+    //
+    Cmd(0xE1),
+    Data(0x0F),
+    Data(0x1B),
+    Data(0x0F),
+    Data(0x17),
+    Data(0x33),
+    Data(0x2C),
+    Data(0x29),
+    Data(0x2e),
+    Data(0x30),
+    Data(0x30),
+    Data(0x39),
+    Data(0x3F),
+    Data(0x00),
+    Data(0x07),
+    Data(0x03),
+    Data(0x10),
+    //
+    Cmd(0xF0), // Enable test command
+    Data(0x01),
+    //
+    Cmd(0xF6), // Disable ram power save mode
+    Data(0x00),
+    //
+    Cmd(0x3A), // 65k mode
+    Data(0x05),
+    //
+    Cmd(SLEEP_OUT),
+    Delay(120),
+    Cmd(DISPLAY_ON),
+];
+
+/// Waveshare 1.3" HAT (ST7789) initialisation from their demo code
+pub const ST7789_INIT_SEQ: [CmdOrData; 30] = [
+    Cmd(SLEEP_OUT),
+    Delay(120),
+    Cmd(PIXEL_FORMAT_SET),
+    Data(0x05),
+    Cmd(0xB2), // Porch setting
+    Data(0x0C),
+    Data(0x0C),
+    Data(0x00),
+    Data(0x33),
+    Data(0x33),
+    Cmd(0xB7), // Gate control
+    Data(0x35),
+    Cmd(0xBB), // VCOM setting
+    Data(0x19),
+    Cmd(0xC0), // LCM control
+    Data(0x2C),
+    Cmd(0xC2), // VDV and VRH command enable
+    Data(0x01),
+    Cmd(0xC3), // VRH set
+    Data(0x12),
+    Cmd(0xC4), // VDV set
+    Data(0x20),
+    Cmd(0xC6), // Frame rate control
+    Data(0x0F),
+    Cmd(0xD0), // Power control 1
+    Data(0xA4),
+    Data(0xA1),
+    Cmd(DISPLAY_INVERSION_ON),
+    Cmd(DISPLAY_ON),
+    Delay(20),
+];
+
+pub const ST7789_GAMMA_SEQ: [CmdOrData; 30] = [
+    Cmd(POSITIVE_GAMMA_CORRECTION),
+    Data(0xD0),
+    Data(0x04),
+    Data(0x0D),
+    Data(0x11),
+    Data(0x13),
+    Data(0x2B),
+    Data(0x3F),
+    Data(0x54),
+    Data(0x4C),
+    Data(0x18),
+    Data(0x0D),
+    Data(0x0B),
+    Data(0x1F),
+    Data(0x23),
+    Cmd(NEGATIVE_GAMMA_CORRECTION),
+    Data(0xD0),
+    Data(0x04),
+    Data(0x0C),
+    Data(0x11),
+    Data(0x13),
+    Data(0x2C),
+    Data(0x3F),
+    Data(0x44),
+    Data(0x51),
+    Data(0x2F),
+    Data(0x1F),
+    Data(0x1F),
+    Data(0x20),
+    Data(0x23),
+];
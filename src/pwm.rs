@@ -1,90 +1,223 @@
 //! PWM module to control LCD display brightness
 //!
+//! Brightness is a percentage, gamma corrected into the PWM duty cycle
+//! so equal steps look equal. The backlight pin uses hardware PWM if it
+//! is on one of the PWM channels (and the pwm overlay is loaded),
+//! otherwise rppal's software PWM. Every change fades over
+//! `backlight.fade_ms`.
+//!
+//! While the panel is idle the level is held down to the idle level,
+//! changes still come through and show when it wakes.
+//!
 //! pwm.rs
 //! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
 //! 01-Jun-2025
 //!
 
+use crate::config::*;
 use crate::defs::*;
 use crate::gpio::*;
 use log::{LevelFilter, debug, error, info, warn};
+use rppal::gpio::OutputPin;
+use rppal::pwm::{Channel, Polarity, Pwm};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-const PERIOD_MS: u64 = 16;
+const FADE_STEP_MS: u64 = 10;
+const IDLE_MS: u64 = 500; // exit checks with nothing to do
+const SOFTWARE_PWM_MAX_HZ: f64 = 2000.0; // a busy thread and jitter above that
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlMode {
-    Toggle = 1,
-    Step = 2,
-    Mid = 3,
-    Off = 4,
-    On = 5,
+    Toggle,  // off, or back to the last level
+    Step,    // up by backlight.step_percent, past 100% to off
+    Mid,     // half of backlight.on_percent
+    Off,     // SIGUSR1
+    On,      // SIGUSR2, backlight.on_percent
+    Set(u8), // percent
+    Dim(u8), // at most this percent until Wake, 0 for off
+    Wake,
 }
 
-pub fn bl_pwm(r: crossbeam_channel::Receiver<BlMode>, m: Arc<Mutex<bool>>) {
-    let mut pulse: u64 = PERIOD_MS / 2; // starting value
-    let mut old_pulse_val: u64 = 0;
+/// Duty cycle for `percent` brightness
+pub fn gamma_duty(percent: f32, gamma: f32) -> f64 {
+    (percent.clamp(0.0, 100.0) / 100.0).powf(gamma) as f64
+}
 
-    gpio_sleep_ms(1000); // wait for BL to switch on before rolling
+// BCM pins that can be routed to a PWM channel
+fn pwm_channel(pin: UBYTE) -> Option<Channel> {
+    match pin {
+        12 | 18 => Some(Channel::Pwm0),
+        13 | 19 => Some(Channel::Pwm1),
+        _ => None,
+    }
+}
 
-    match gpio_get_output_pin(LCD_BL) {
-        Ok(mut out_pin) => {
-            loop {
-                if old_pulse_val != pulse {
-                    out_pin
-                        .set_pwm(
-                            Duration::from_millis(PERIOD_MS),
-                            Duration::from_millis(pulse),
-                        )
-                        .expect("Error: bl_pwm(): pin.set_pwm()");
-
-                    debug!("{}(): pulse value: {pulse}", func_name!());
-                    old_pulse_val = pulse;
-                }
-                match r.recv() {
-                    Ok(BlMode::Toggle) => {
-                        if pulse > 0 {
-                            pulse = 0;
-                        } else {
-                            pulse = PERIOD_MS / 2;
-                        }
-                        debug!("{}(): got: {:#?} {pulse}", func_name!(), 1);
-                    }
-                    Ok(BlMode::Step) => {
-                        pulse += 2;
-
-                        if !(1..=PERIOD_MS).contains(&pulse) {
-                            pulse = 0;
-                        }
-                        debug!("{}(): got: {:#?} {pulse}", func_name!(), 2);
-                    }
-                    Ok(BlMode::Mid) => {
-                        // from gpio_init()
-                        pulse = PERIOD_MS / 4;
-                    }
-                    Ok(BlMode::Off) => {
-                        // SIGUSR1
-                        pulse = 0;
-                    }
-                    Ok(BlMode::On) => {
-                        // SIGUSR2
-                        pulse = PERIOD_MS / 4;
-                    }
-                    _ => {}
-                }
-                let _exit = m.lock().unwrap();
-                if *_exit {
-                    info!("Exiting {}()", func_name!());
-                    break;
+/// The backlight pin, on the PWM hardware or driven in software
+enum Backlight {
+    Hardware(Pwm),
+    Software(OutputPin, f64), // Hz
+}
+
+impl Backlight {
+    fn open(pin: UBYTE, hz: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(channel) = pwm_channel(pin) {
+            match Pwm::with_frequency(channel, hz, 0.0, Polarity::Normal, true) {
+                Ok(pwm) => {
+                    info!("{}(): GPIO{} on hardware {}", func_name!(), pin, channel);
+                    return Ok(Backlight::Hardware(pwm));
                 }
-                drop(_exit);
-                thread::sleep(Duration::from_millis(300));
+                Err(e) => warn!(
+                    "{}(): GPIO{}: no hardware PWM ({}), using software",
+                    func_name!(),
+                    pin,
+                    e
+                ),
             }
         }
+        if hz > SOFTWARE_PWM_MAX_HZ {
+            warn!(
+                "{}(): GPIO{}: {} Hz is too fast for software PWM, using {}",
+                func_name!(),
+                pin,
+                hz,
+                SOFTWARE_PWM_MAX_HZ
+            );
+        }
+        let hz = hz.min(SOFTWARE_PWM_MAX_HZ);
+        Ok(Backlight::Software(gpio_get_output_pin(pin)?, hz))
+    }
+
+    fn set_duty(&mut self, duty: f64) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Backlight::Hardware(pwm) => Ok(pwm.set_duty_cycle(duty)?),
+            // Fully off and fully on without the PWM thread
+            Backlight::Software(pin, _) if duty <= 0.0 => {
+                pin.clear_pwm()?;
+                pin.set_low();
+                Ok(())
+            }
+            Backlight::Software(pin, _) if duty >= 1.0 => {
+                pin.clear_pwm()?;
+                pin.set_high();
+                Ok(())
+            }
+            Backlight::Software(pin, hz) => Ok(pin.set_pwm_frequency(*hz, duty)?),
+        }
+    }
+}
+
+/// Where the brightness is and where it is going
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    start: Instant,
+    length: Duration,
+}
+
+impl Fade {
+    fn level(&self, now: Instant) -> f32 {
+        let t = now.duration_since(self.start).as_secs_f32() / self.length.as_secs_f32();
+        if t >= 1.0 || !t.is_finite() {
+            self.to
+        } else {
+            self.from + (self.to - self.from) * t
+        }
+    }
+
+    fn done(&self, now: Instant) -> bool {
+        now >= self.start + self.length
+    }
+}
+
+/// Backlight thread, brightness changes come in on `r`
+pub fn bl_pwm(config: SharedConfig, r: crossbeam_channel::Receiver<BlMode>, m: Arc<Mutex<bool>>) {
+    let (bl_pin, mut bl) = {
+        let config = config.lock().unwrap();
+        (config.pins.lcd_bl, config.backlight.clone())
+    };
+
+    gpio_sleep_ms(1000); // wait for BL to switch on before rolling
+
+    let mut backlight = match Backlight::open(bl_pin, bl.pwm_hz as f64) {
+        Ok(backlight) => backlight,
         Err(e) => {
             error!("{}(): {:?}", func_name!(), e);
+            return;
+        }
+    };
+
+    let mut target = bl.on_percent as f32;
+    let mut last_on = target; // for Toggle
+    let mut cap: Option<f32> = None; // idle level
+    let mut fade = Fade {
+        from: 0.0,
+        to: target,
+        start: Instant::now(),
+        length: bl.fade(),
+    };
+    let mut duty = -1.0;
+
+    loop {
+        bl = config.lock().unwrap().backlight.clone();
+        let now = Instant::now();
+        let new_duty = gamma_duty(fade.level(now), bl.gamma);
+        if new_duty != duty {
+            if let Err(e) = backlight.set_duty(new_duty) {
+                error!("{}(): {:?}", func_name!(), e);
+            }
+            duty = new_duty;
+        }
+
+        let timeout = if fade.done(now) {
+            IDLE_MS
+        } else {
+            FADE_STEP_MS
+        };
+        if let Ok(mode) = r.recv_timeout(Duration::from_millis(timeout)) {
+            match mode {
+                BlMode::Dim(percent) => cap = Some(percent.min(100) as f32),
+                BlMode::Wake => cap = None,
+                _ => {}
+            }
+            target = match mode {
+                BlMode::Toggle if target > 0.0 => 0.0,
+                BlMode::Toggle => last_on,
+                BlMode::Step if target >= 100.0 => 0.0,
+                BlMode::Step => (target + bl.step_percent as f32).min(100.0),
+                BlMode::Mid => bl.on_percent as f32 / 2.0,
+                BlMode::Off => 0.0,
+                BlMode::On => bl.on_percent as f32,
+                BlMode::Set(percent) => percent.min(100) as f32,
+                BlMode::Dim(_) | BlMode::Wake => target,
+            };
+            if target > 0.0 {
+                last_on = target;
+            }
+            debug!(
+                "{}(): {:?}, {}%, idle {:?}",
+                func_name!(),
+                mode,
+                target,
+                cap
+            );
+            let now = Instant::now();
+            fade = Fade {
+                from: fade.level(now),
+                to: cap.map_or(target, |cap| target.min(cap)),
+                start: now,
+                length: bl.fade(),
+            };
+        }
+
+        let _exit = m.lock().unwrap();
+        if *_exit {
+            info!("Exiting {}()", func_name!());
+            break;
         }
+        drop(_exit);
     }
 }
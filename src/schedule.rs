@@ -0,0 +1,315 @@
+//! Backlight schedule: brightness levels at times of day, or at sunrise
+//! and sunset worked out from the configured latitude and longitude.
+//!
+//! The schedule sets the level at each transition and leaves it alone in
+//! between, so SIGUSR1/SIGUSR2 and the keys still work as overrides
+//! until the next transition. This replaces the old LCD_crontab.
+//!
+//! schedule.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 23-Jul-2025
+//!
+
+use crate::config::*;
+use crate::defs::*;
+use crate::pwm::BlMode;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use crossbeam_channel::Sender;
+use log::{LevelFilter, debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+const POLL_MS: u64 = 1000;
+
+// Far enough back to find the level in effect with rules on one day a
+// week only
+const LOOK_BACK_DAYS: u64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+pub const DAYS: [Day; 7] = [
+    Day::Mon,
+    Day::Tue,
+    Day::Wed,
+    Day::Thu,
+    Day::Fri,
+    Day::Sat,
+    Day::Sun,
+];
+
+impl From<Weekday> for Day {
+    fn from(weekday: Weekday) -> Self {
+        DAYS[weekday.num_days_from_monday() as usize]
+    }
+}
+
+/// When a rule fires: "HH:MM" local time, "sunrise" or "sunset"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum At {
+    Time(NaiveTime),
+    Sunrise,
+    Sunset,
+}
+
+impl TryFrom<String> for At {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "sunrise" => Ok(At::Sunrise),
+            "sunset" => Ok(At::Sunset),
+            _ => NaiveTime::parse_from_str(&s, "%H:%M")
+                .map(At::Time)
+                .map_err(|_| format!("\"{}\" is not HH:MM, sunrise or sunset", s)),
+        }
+    }
+}
+
+impl From<At> for String {
+    fn from(at: At) -> Self {
+        at.to_string()
+    }
+}
+
+impl fmt::Display for At {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            At::Time(time) => write!(f, "{}", time.format("%H:%M")),
+            At::Sunrise => write!(f, "sunrise"),
+            At::Sunset => write!(f, "sunset"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub at: At,
+    #[serde(default)]
+    pub offset_mins: i32, // from sunrise or sunset, - for before
+    #[serde(default = "every_day")]
+    pub days: Vec<Day>,
+    pub percent: u8,
+}
+
+fn every_day() -> Vec<Day> {
+    DAYS.to_vec()
+}
+
+/// What LCD_crontab did: on in the morning, off at night
+pub fn default_rules() -> Vec<Rule> {
+    [("07:00", BACKLIGHT_ON_PERCENT), ("21:00", 0)]
+        .into_iter()
+        .map(|(at, percent)| Rule {
+            at: At::try_from(at.to_string()).unwrap(),
+            offset_mins: 0,
+            days: every_day(),
+            percent,
+        })
+        .collect()
+}
+
+/// One message per problem
+pub fn validate_schedule(schedule: &ScheduleConfig, errors: &mut Vec<String>) {
+    if let Some(latitude) = schedule.latitude
+        && !(-90.0..=90.0).contains(&latitude)
+    {
+        errors.push(format!("schedule.latitude: {} is not -90 to 90", latitude));
+    }
+    if let Some(longitude) = schedule.longitude
+        && !(-180.0..=180.0).contains(&longitude)
+    {
+        errors.push(format!(
+            "schedule.longitude: {} is not -180 to 180",
+            longitude
+        ));
+    }
+    for (i, rule) in schedule.rules.iter().enumerate() {
+        if rule.percent > 100 {
+            errors.push(format!(
+                "schedule.rules[{}]: percent {} is more than 100",
+                i, rule.percent
+            ));
+        }
+        if rule.days.is_empty() {
+            errors.push(format!("schedule.rules[{}]: days must not be empty", i));
+        }
+        match rule.at {
+            At::Time(_) if rule.offset_mins != 0 => errors.push(format!(
+                "schedule.rules[{}]: offset_mins is only for sunrise and sunset",
+                i
+            )),
+            At::Sunrise | At::Sunset
+                if schedule.latitude.is_none() || schedule.longitude.is_none() =>
+            {
+                errors.push(format!(
+                    "schedule.rules[{}]: {} needs schedule.latitude and schedule.longitude",
+                    i, rule.at
+                ))
+            }
+            _ => {}
+        }
+        if rule.offset_mins.abs() > 12 * 60 {
+            errors.push(format!(
+                "schedule.rules[{}]: offset_mins must be within 720 either way",
+                i
+            ));
+        }
+    }
+}
+
+/// Sunrise and sunset on `date` at `latitude`, `longitude` (east and
+/// north positive), from the sunrise equation. None when the sun
+/// doesn't rise or doesn't set that day.
+pub fn sun_times(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    const J2000: f64 = 2451545.0;
+    const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+    let noon_jd =
+        date.and_hms_opt(12, 0, 0)?.and_utc().timestamp() as f64 / 86400.0 + UNIX_EPOCH_JD;
+    let mean_noon = (noon_jd - J2000).round() - longitude / 360.0;
+
+    let anomaly = (357.5291 + 0.98560028 * mean_noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let centre =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + centre + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit =
+        J2000 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    // The sun's centre 0.833 degrees below the horizon, for refraction
+    // and its radius
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+
+    let utc = |jd: f64| {
+        Utc.timestamp_opt(((jd - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0)
+            .single()
+    };
+    Some((utc(transit - half_day)?, utc(transit + half_day)?))
+}
+
+/// A level and when it starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub at: DateTime<Local>,
+    pub percent: u8,
+    pub rule: usize, // index into schedule.rules
+}
+
+/// When `rule` fires on `date`, if it does
+fn rule_time(schedule: &ScheduleConfig, rule: &Rule, date: NaiveDate) -> Option<DateTime<Local>> {
+    if !rule.days.contains(&date.weekday().into()) {
+        return None;
+    }
+    match rule.at {
+        // Skipped if the clocks go forward over it
+        At::Time(time) => date.and_time(time).and_local_timezone(Local).earliest(),
+        At::Sunrise | At::Sunset => {
+            let (sunrise, sunset) = sun_times(date, schedule.latitude?, schedule.longitude?)?;
+            let sun = if rule.at == At::Sunrise {
+                sunrise
+            } else {
+                sunset
+            };
+            let at = sun + chrono::Duration::minutes(rule.offset_mins as i64);
+            Some(at.with_timezone(&Local))
+        }
+    }
+}
+
+/// Transitions from the start of `first` for `days` days, in order
+pub fn transitions(schedule: &ScheduleConfig, first: NaiveDate, days: u64) -> Vec<Transition> {
+    let mut transitions: Vec<Transition> = (0..days)
+        .filter_map(|n| first.checked_add_days(Days::new(n)))
+        .flat_map(|date| {
+            schedule
+                .rules
+                .iter()
+                .enumerate()
+                .filter_map(move |(i, rule)| {
+                    rule_time(schedule, rule, date).map(|at| Transition {
+                        at,
+                        percent: rule.percent,
+                        rule: i,
+                    })
+                })
+        })
+        .collect();
+    transitions.sort_by_key(|t| (t.at, t.rule));
+    transitions
+}
+
+/// The transition in effect at `now`, None without rules
+pub fn current_transition(schedule: &ScheduleConfig, now: DateTime<Local>) -> Option<Transition> {
+    let first = now
+        .date_naive()
+        .checked_sub_days(Days::new(LOOK_BACK_DAYS))?;
+    transitions(schedule, first, LOOK_BACK_DAYS + 2)
+        .into_iter()
+        .take_while(|t| t.at <= now)
+        .last()
+}
+
+/// Sets the backlight level at every transition, on `s` to bl_pwm(). At
+/// startup and when a reload moves the transition in effect it sets the
+/// level straight away.
+pub fn bl_schedule(config: SharedConfig, s: Sender<BlMode>, m: Arc<Mutex<bool>>) {
+    let mut applied: Option<Transition> = None;
+
+    loop {
+        let schedule = config.lock().unwrap().schedule.clone();
+        let current = current_transition(&schedule, Local::now());
+        if let Some(transition) = current
+            && current.map(|t| (t.at, t.rule)) != applied.map(|t| (t.at, t.rule))
+        {
+            info!(
+                "{}(): {} at {}, {}%",
+                func_name!(),
+                schedule.rules[transition.rule].at,
+                transition.at.format("%a %H:%M"),
+                transition.percent
+            );
+            if s.send(BlMode::Set(transition.percent)).is_err() {
+                break;
+            }
+        }
+        applied = current;
+
+        let _exit = m.lock().unwrap();
+        if *_exit {
+            info!("Exiting {}()", func_name!());
+            break;
+        }
+        drop(_exit);
+        thread::sleep(Duration::from_millis(POLL_MS));
+    }
+}
@@ -0,0 +1,385 @@
+//! Off-screen simulator for the LCD controller. Interprets the command
+//! stream `Lcd` emits (CASET/RASET/RAMWR/MADCTL...) into a virtual GRAM
+//! and dumps the visible panel area as a PNG or PPM file.
+//!
+//! MADCTL model: MX/MY mirror the column/row address counters, MV then
+//! exchanges them, BGR swaps red and blue against the panel's colour order.
+//! The dump reads the GRAM back through the panel's upright MADCTL and
+//! window offset, so it shows what someone holding the HAT would see.
+//!
+//! sim.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 02-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::lcd::lcd::*;
+use crate::panel::*;
+use log::{LevelFilter, debug, error, info, warn};
+use rppal::gpio::Level;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const MADCTL_MY: UBYTE = 0x80;
+const MADCTL_MX: UBYTE = 0x40;
+const MADCTL_MV: UBYTE = 0x20;
+const MADCTL_BGR: UBYTE = 0x08;
+
+/// Simulated ST7735/ILI9341 controller with its GRAM
+pub struct SimBus {
+    gram_width: usize,
+    gram_height: usize,
+    gram: Vec<UWORD>,
+    viewport: (usize, usize, usize, usize), // x, y, w, h of the glass within GRAM
+    panel_bgr: bool,
+    panel_inverted: bool,
+    upright: (UBYTE, (usize, usize), (usize, usize)), // MADCTL, window offset and size seen upright
+    madctl: UBYTE,
+    colmod: UBYTE,
+    cmd: Option<UBYTE>,
+    params: Vec<UBYTE>,
+    col: (usize, usize),
+    row: (usize, usize),
+    cur: (usize, usize),
+    pixel_hi: Option<UBYTE>,
+    cs_active: bool,
+    rst_low: bool,
+    sleeping: bool,
+    display_on: bool,
+    inverted: bool,
+    out_of_bounds: usize,
+}
+
+impl SimBus {
+    pub fn new(gram_width: usize, gram_height: usize) -> Self {
+        Self {
+            gram_width,
+            gram_height,
+            gram: vec![BLACK; gram_width * gram_height],
+            viewport: (0, 0, gram_width, gram_height),
+            panel_bgr: true,
+            panel_inverted: false,
+            upright: (0x00, (0, 0), (gram_width, gram_height)),
+            madctl: 0x00,
+            colmod: 0x06,
+            cmd: None,
+            params: Vec::new(),
+            col: (0, gram_width - 1),
+            row: (0, gram_height - 1),
+            cur: (0, 0),
+            pixel_hi: None,
+            cs_active: true,
+            rst_low: false,
+            sleeping: true,
+            display_on: false,
+            inverted: false,
+            out_of_bounds: 0,
+        }
+    }
+
+    /// Controller, glass and upright scan direction of a panel profile
+    pub fn for_panel(panel: &PanelProfile) -> Self {
+        let mut sim = Self::new(panel.gram_width, panel.gram_height)
+            .with_viewport(0, 0, panel.width, panel.height)
+            .with_panel_bgr(panel.colour_order == ColourOrder::Bgr)
+            .with_panel_inverted(panel.inverted);
+        let madctl = panel.madctl_value(panel.orientation);
+        let (x, y) = panel.offset(panel.orientation);
+        let size = if madctl & MADCTL_MV != 0 {
+            (panel.height, panel.width)
+        } else {
+            (panel.width, panel.height)
+        };
+        sim.upright = (madctl, (x as usize, y as usize), size);
+        sim
+    }
+
+    /// Waveshare 1.44" LCD HAT (ST7735S, 128x128)
+    pub fn st7735s_144() -> Self {
+        Self::for_panel(&WAVESHARE_144)
+    }
+
+    /// Waveshare 1.3" LCD HAT (ST7789, 240x240)
+    pub fn st7789_13() -> Self {
+        Self::for_panel(&WAVESHARE_13)
+    }
+
+    /// 2.4"/2.8" ILI9341 panels (240x320)
+    pub fn ili9341() -> Self {
+        Self::for_panel(&ILI9341_240X320)
+    }
+
+    /// Area of the GRAM that is actually visible on the glass
+    pub fn with_viewport(mut self, x: usize, y: usize, w: usize, h: usize) -> Self {
+        self.viewport = (x, y, w, h);
+        self.upright = (0x00, (x, y), (w, h));
+        self
+    }
+
+    /// Colour order of the glass, true if it is wired BGR
+    pub fn with_panel_bgr(mut self, bgr: bool) -> Self {
+        self.panel_bgr = bgr;
+        self
+    }
+
+    /// True for IPS glass that needs display inversion on for true colours
+    pub fn with_panel_inverted(mut self, inverted: bool) -> Self {
+        self.panel_inverted = inverted;
+        self
+    }
+
+    pub fn madctl(&self) -> UBYTE {
+        self.madctl
+    }
+
+    pub fn is_display_on(&self) -> bool {
+        self.display_on && !self.sleeping
+    }
+
+    /// Pixels written outside the GRAM, non-zero usually means the
+    /// window offsets are wrong.
+    pub fn out_of_bounds(&self) -> usize {
+        self.out_of_bounds
+    }
+
+    /// Raw GRAM pixel in controller coordinates
+    pub fn gram_pixel(&self, x: usize, y: usize) -> Option<UWORD> {
+        if x < self.gram_width && y < self.gram_height {
+            Some(self.gram[y * self.gram_width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Colour seen on the glass at viewport position (x, y)
+    pub fn panel_pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let (vx, vy, _, _) = self.viewport;
+        self.glass_colour(self.gram_pixel(vx + x, vy + y))
+    }
+
+    fn glass_colour(&self, raw: Option<UWORD>) -> (u8, u8, u8) {
+        if !self.is_display_on() {
+            return (0, 0, 0);
+        }
+        let raw = raw.unwrap_or(BLACK);
+        let (mut r, g, mut b) = u16_to_rgb(if self.inverted != self.panel_inverted {
+            !raw
+        } else {
+            raw
+        });
+        if ((self.madctl & MADCTL_BGR) != 0) != self.panel_bgr {
+            std::mem::swap(&mut r, &mut b);
+        }
+        (r, g, b)
+    }
+
+    /// Size of the dumped image, as seen upright
+    pub fn image_size(&self) -> (usize, usize) {
+        self.upright.2
+    }
+
+    /// Visible panel area as packed RGB888, as seen upright
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let (madctl, (off_x, off_y), (out_w, out_h)) = self.upright;
+        let mut data = Vec::with_capacity(out_w * out_h * 3);
+        for oy in 0..out_h {
+            for ox in 0..out_w {
+                let (x, y) = self.map_address_with(madctl, off_x + ox, off_y + oy);
+                let (r, g, b) = self.glass_colour(self.gram_pixel(x, y));
+                data.extend_from_slice(&[r, g, b]);
+            }
+        }
+        data
+    }
+
+    pub fn write_ppm(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let (w, h) = self.image_size();
+        let mut f = BufWriter::new(File::create(path)?);
+        write!(f, "P6\n{} {}\n255\n", w, h)?;
+        f.write_all(&self.to_rgb())?;
+        f.flush()?;
+        Ok(())
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let (w, h) = self.image_size();
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(File::create(path)?), w as u32, h as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgb())?;
+        Ok(())
+    }
+
+    /// PNG or PPM, picked by file extension
+    pub fn write_image(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("ppm") => self.write_ppm(path),
+            _ => self.write_png(path),
+        }?;
+        info!(
+            "{}(): wrote {} (MADCTL 0x{:02X}, {} pixels out of bounds)",
+            func_name!(),
+            path.display(),
+            self.madctl,
+            self.out_of_bounds
+        );
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.gram.fill(BLACK);
+        self.madctl = 0x00;
+        self.cmd = None;
+        self.params.clear();
+        self.col = (0, self.gram_width - 1);
+        self.row = (0, self.gram_height - 1);
+        self.cur = (0, 0);
+        self.pixel_hi = None;
+        self.sleeping = true;
+        self.display_on = false;
+        self.inverted = false;
+    }
+
+    // Logical (column, row) to GRAM (x, y), see MADCTL model above
+    fn map_address(&self, col: usize, row: usize) -> (usize, usize) {
+        self.map_address_with(self.madctl, col, row)
+    }
+
+    fn map_address_with(&self, madctl: UBYTE, col: usize, row: usize) -> (usize, usize) {
+        let mv = madctl & MADCTL_MV != 0;
+        let (col_range, row_range) = if mv {
+            (self.gram_height, self.gram_width)
+        } else {
+            (self.gram_width, self.gram_height)
+        };
+        let c = if madctl & MADCTL_MX != 0 {
+            (col_range - 1).wrapping_sub(col)
+        } else {
+            col
+        };
+        let r = if madctl & MADCTL_MY != 0 {
+            (row_range - 1).wrapping_sub(row)
+        } else {
+            row
+        };
+        if mv { (r, c) } else { (c, r) }
+    }
+
+    fn write_pixel(&mut self, colour: UWORD) {
+        let (col, row) = self.cur;
+        let (x, y) = self.map_address(col, row);
+        if x < self.gram_width && y < self.gram_height {
+            self.gram[y * self.gram_width + x] = colour;
+        } else {
+            self.out_of_bounds += 1;
+        }
+
+        // Address counter: column first, then row, wrapping to the window
+        self.cur.0 += 1;
+        if self.cur.0 > self.col.1 {
+            self.cur.0 = self.col.0;
+            self.cur.1 += 1;
+            if self.cur.1 > self.row.1 {
+                self.cur.1 = self.row.0;
+            }
+        }
+    }
+
+    fn apply_params(&mut self) {
+        let Some(cmd) = self.cmd else {
+            return;
+        };
+        let p = &self.params;
+        match cmd {
+            COLUMN_ADDRESS_SET if p.len() == 4 => {
+                self.col = (
+                    ((p[0] as usize) << 8) | p[1] as usize,
+                    ((p[2] as usize) << 8) | p[3] as usize,
+                );
+            }
+            ROW_ADDRESS_SET if p.len() == 4 => {
+                self.row = (
+                    ((p[0] as usize) << 8) | p[1] as usize,
+                    ((p[2] as usize) << 8) | p[3] as usize,
+                );
+            }
+            MEMORY_ACCESS_CONTROL if p.len() == 1 => {
+                self.madctl = p[0];
+            }
+            PIXEL_FORMAT_SET if p.len() == 1 => {
+                self.colmod = p[0];
+                if self.colmod & 0x07 != 0x05 {
+                    warn!(
+                        "{}(): pixel format 0x{:02X} is not RGB565",
+                        func_name!(),
+                        self.colmod
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl DisplayBus for SimBus {
+    fn write_cmd(&mut self, cmd: UBYTE) -> Result<(), LcdError> {
+        if !self.cs_active || self.rst_low {
+            return Ok(());
+        }
+        self.cmd = Some(cmd);
+        self.params.clear();
+        self.pixel_hi = None;
+        match cmd {
+            SLEEP_OUT => self.sleeping = false,
+            ENTER_SLEEP_MODE => self.sleeping = true,
+            DISPLAY_ON => self.display_on = true,
+            DISPLAY_OFF => self.display_on = false,
+            DISPLAY_INVERSION_ON => self.inverted = true,
+            DISPLAY_INVERSION_OFF => self.inverted = false,
+            MEMORY_WRITE => self.cur = (self.col.0, self.row.0),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn write_data(&mut self, data: &[UBYTE]) -> Result<(), LcdError> {
+        if !self.cs_active || self.rst_low {
+            return Ok(());
+        }
+        if self.cmd == Some(MEMORY_WRITE) {
+            for byte in data {
+                match self.pixel_hi.take() {
+                    Some(hi) => self.write_pixel(((hi as UWORD) << 8) | *byte as UWORD),
+                    None => self.pixel_hi = Some(*byte),
+                }
+            }
+        } else {
+            self.params.extend_from_slice(data);
+            self.apply_params();
+        }
+        Ok(())
+    }
+
+    fn set_pin(&mut self, pin: LcdPin, level: Level) -> Result<(), LcdError> {
+        match pin {
+            LcdPin::Cs => self.cs_active = level == Level::Low,
+            LcdPin::Rst => {
+                if level == Level::Low {
+                    self.rst_low = true;
+                    self.reset();
+                } else {
+                    self.rst_low = false;
+                }
+            }
+            LcdPin::Dc | LcdPin::Bl => {}
+        }
+        Ok(())
+    }
+
+    fn delay_ms(&mut self, _ms: u64) {}
+}
@@ -0,0 +1,240 @@
+//! Bitmap sprites: BMP, PNG and QOI images converted to RGB565 once, at
+//! startup or from `include_bytes!`, and blitted onto the `Lcd` image
+//! buffer with colour-key or alpha transparency.
+//!
+//! sprite.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 14-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::graphics::to_u16;
+use crate::lcd::lcd::*;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use log::{LevelFilter, debug, error, info, warn};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tinybmp::Bmp;
+
+#[derive(Debug)]
+pub enum SpriteError {
+    Read(PathBuf, std::io::Error),
+    Decode(String),
+    Unsupported, // not a BMP, PNG or QOI file
+}
+
+impl fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpriteError::Read(path, e) => write!(f, "can't read {}: {}", path.display(), e),
+            SpriteError::Decode(e) => write!(f, "can't decode image: {}", e),
+            SpriteError::Unsupported => write!(f, "not a BMP, PNG or QOI image"),
+        }
+    }
+}
+
+impl std::error::Error for SpriteError {}
+
+/// RGB565 image, with per-pixel alpha if the file had any
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite {
+    width: usize,
+    height: usize,
+    pixels: Vec<UWORD>,
+    alpha: Option<Vec<u8>>,
+    colour_key: Option<UWORD>,
+}
+
+impl Sprite {
+    /// Decode a BMP, PNG or QOI image, the format is taken from its header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SpriteError> {
+        if bytes.starts_with(b"\x89PNG") {
+            Self::from_png(bytes)
+        } else if bytes.starts_with(b"qoif") {
+            Self::from_qoi(bytes)
+        } else if bytes.starts_with(b"BM") {
+            Self::from_bmp(bytes)
+        } else {
+            Err(SpriteError::Unsupported)
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SpriteError> {
+        let bytes = std::fs::read(path).map_err(|e| SpriteError::Read(path.to_path_buf(), e))?;
+        let sprite = Self::from_bytes(&bytes)?;
+        debug!(
+            "{}(): {} {}x{}{}",
+            func_name!(),
+            path.display(),
+            sprite.width,
+            sprite.height,
+            if sprite.alpha.is_some() { " alpha" } else { "" }
+        );
+        Ok(sprite)
+    }
+
+    /// Pixels of `colour` are not drawn
+    pub fn with_colour_key(mut self, colour: UWORD) -> Self {
+        self.colour_key = Some(colour);
+        self
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn from_png(bytes: &[u8]) -> Result<Self, SpriteError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| SpriteError::Decode(e.to_string()))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| SpriteError::Decode(e.to_string()))?;
+
+        // Expanded to 8 bits, palette to RGB(A)
+        let rgba: Vec<[u8; 4]> = match info.color_type {
+            png::ColorType::Grayscale => buf.iter().map(|&v| [v, v, v, 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Rgb => buf
+                .chunks_exact(3)
+                .map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::Rgba => buf
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect(),
+            png::ColorType::Indexed => {
+                return Err(SpriteError::Decode("indexed PNG not expanded".to_string()));
+            }
+        };
+        Ok(Self::from_rgba(
+            info.width as usize,
+            info.height as usize,
+            &rgba[..(info.width * info.height) as usize],
+        ))
+    }
+
+    fn from_qoi(bytes: &[u8]) -> Result<Self, SpriteError> {
+        let (header, buf) =
+            qoi::decode_to_vec(bytes).map_err(|e| SpriteError::Decode(e.to_string()))?;
+        let rgba: Vec<[u8; 4]> = match header.channels {
+            qoi::Channels::Rgb => buf
+                .chunks_exact(3)
+                .map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            qoi::Channels::Rgba => buf
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect(),
+        };
+        Ok(Self::from_rgba(
+            header.width as usize,
+            header.height as usize,
+            &rgba,
+        ))
+    }
+
+    // Opaque, use a colour key for transparency
+    fn from_bmp(bytes: &[u8]) -> Result<Self, SpriteError> {
+        let bmp = Bmp::<Rgb565>::from_slice(bytes)
+            .map_err(|e| SpriteError::Decode(format!("{:?}", e)))?;
+        let size = bmp.as_raw().header().image_size;
+        let (width, height) = (size.width as usize, size.height as usize);
+        let mut pixels = vec![BLACK; width * height];
+        for Pixel(p, colour) in bmp.pixels() {
+            pixels[p.y as usize * width + p.x as usize] = to_u16(colour);
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            alpha: None,
+            colour_key: None,
+        })
+    }
+
+    // Alpha is dropped if every pixel is opaque
+    fn from_rgba(width: usize, height: usize, rgba: &[[u8; 4]]) -> Self {
+        let alpha: Vec<u8> = rgba.iter().map(|p| p[3]).collect();
+        Self {
+            width,
+            height,
+            pixels: rgba.iter().map(|p| rgb_to_u16(p[0], p[1], p[2])).collect(),
+            alpha: alpha.iter().any(|&a| a < 255).then_some(alpha),
+            colour_key: None,
+        }
+    }
+}
+
+impl<B: DisplayBus> Lcd<B> {
+    /// Blit `sprite` with its top left corner at (x, y), clipped. Skips
+    /// colour-keyed pixels and blends translucent ones with the canvas.
+    pub fn img_draw_bitmap(&mut self, x: i32, y: i32, sprite: &Sprite) -> &Self {
+        for j in 0..sprite.height {
+            for i in 0..sprite.width {
+                let (px, py) = (x + i as i32, y + j as i32);
+                if px < 0 || py < 0 {
+                    continue;
+                }
+                let (px, py) = (px as usize, py as usize);
+                let idx = j * sprite.width + i;
+                let colour = sprite.pixels[idx];
+                if sprite.colour_key == Some(colour) {
+                    continue;
+                }
+                let alpha = sprite.alpha.as_ref().map_or(255, |a| a[idx]);
+                self.img_blend_pixel(px, py, colour, alpha);
+            }
+        }
+        self
+    }
+
+    /// The same over a solid `bg`, colour-keyed pixels in `bg`, each
+    /// pixel written once
+    pub fn img_draw_bitmap_on(&mut self, x: i32, y: i32, sprite: &Sprite, bg: UWORD) -> &Self {
+        for j in 0..sprite.height {
+            for i in 0..sprite.width {
+                let (px, py) = (x + i as i32, y + j as i32);
+                if px < 0 || py < 0 {
+                    continue;
+                }
+                let idx = j * sprite.width + i;
+                let colour = sprite.pixels[idx];
+                let alpha = if sprite.colour_key == Some(colour) {
+                    0
+                } else {
+                    sprite.alpha.as_ref().map_or(255, |a| a[idx])
+                };
+                self.img_draw_pixel(px as usize, py as usize, alpha_blend(colour, bg, alpha));
+            }
+        }
+        self
+    }
+}
+
+/// Dashboard icons, built in
+pub struct Icons {
+    pub wifi: Sprite,
+    pub temp: Sprite,
+    pub battery: Sprite,
+    pub bitcoin: Sprite,
+}
+
+pub static ICONS: LazyLock<Icons> = LazyLock::new(|| {
+    let icon = |bytes: &[u8]| Sprite::from_bytes(bytes).expect("Error: built-in icon");
+    Icons {
+        wifi: icon(include_bytes!("icons/wifi.png")),
+        temp: icon(include_bytes!("icons/temp.png")),
+        battery: icon(include_bytes!("icons/battery.png")),
+        bitcoin: icon(include_bytes!("icons/bitcoin.png")),
+    }
+});
@@ -11,6 +11,28 @@ use log::{LevelFilter, debug, error, info, warn};
 use std::sync::Arc;
 use std::sync::Mutex;
 
+/// Every key in the JSON object, for the screen layouts
+pub const STAT_KEYS: [&str; 18] = [
+    _J_TIME,
+    _J_IP_ADDRESS,
+    _J_UPTIME,
+    _J_LOAD,
+    _J_CPU_TEMP,
+    _J_UPS_TIME,
+    _J_ON_BATTERY,
+    _J_BATTERY_PERCENT,
+    _J_NET_STATUS,
+    _J_TIME_REMAINING_OR_TO_FULL,
+    _J_PROCESS_NAME,
+    _J_PROCESS_STATUS,
+    _J_BTC_CMP,
+    _J_BTC_ATH,
+    _J_BTC_CMP_ATH_DIFF,
+    _J_BTC_CMP_STR,
+    _J_BTC_ATH_STR,
+    _J_BTC_CMP_ATH_DIFF_STR,
+];
+
 pub fn get_json_obj(crypto_result: Arc<Mutex<CryptoResult>>) -> json::JsonValue {
     let c_r_p = crypto_result.lock().unwrap();
     let c_r: CryptoResult = c_r_p.clone();
@@ -0,0 +1,443 @@
+//! Text on the `Lcd` image buffer. The `Font` trait covers the built-in
+//! `FontTable`s, the anti-aliased `AlphaFont`s and BDF/PSF fonts loaded
+//! from disk (see fontfile.rs). Glyphs are 1-bit or 4-/8-bit alpha,
+//! blended over a solid background or over whatever is already there.
+//!
+//! The built-in alpha fonts are the 1-bit fonts scaled down with a box
+//! filter, the large fonts come out as smooth mid-size ones.
+//!
+//! text.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 15-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::fonts::font24::*;
+use crate::fonts::font48::*;
+use crate::fonts::font50::*;
+use crate::glyphs::*;
+use crate::lcd::lcd::*;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// 9x12, from FONT24
+pub static AA_FONT12: LazyLock<AlphaFont> =
+    LazyLock::new(|| AlphaFont::from_font_table(&FONT24, 2, AlphaDepth::Four));
+/// 10x16, from FONT48
+pub static AA_FONT16: LazyLock<AlphaFont> =
+    LazyLock::new(|| AlphaFont::from_font_table(&FONT48, 3, AlphaDepth::Four));
+/// 14x24, from FONT48
+pub static AA_FONT24: LazyLock<AlphaFont> =
+    LazyLock::new(|| AlphaFont::from_font_table(&FONT48, 2, AlphaDepth::Four));
+/// 14x25, from FONT50
+pub static AA_FONT25: LazyLock<AlphaFont> =
+    LazyLock::new(|| AlphaFont::from_font_table(&FONT50, 2, AlphaDepth::Four));
+
+/// Anything text can be drawn with
+pub trait Font {
+    /// Line height in pixels
+    fn height(&self) -> usize;
+
+    /// None if the font has no glyph for `c`
+    fn glyph(&self, c: char) -> Option<Glyph<'_>>;
+
+    /// Pen adjustment between `left` and `right`, negative pulls `right`
+    /// closer
+    fn kerning(&self, left: char, right: char) -> i32 {
+        0
+    }
+}
+
+impl<F: Font + ?Sized> Font for &F {
+    fn height(&self) -> usize {
+        (**self).height()
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        (**self).glyph(c)
+    }
+
+    fn kerning(&self, left: char, right: char) -> i32 {
+        (**self).kerning(left, right)
+    }
+}
+
+/// Glyph for `c`, or the replacement glyph, or '?' if the font lacks both
+pub fn glyph_or_fallback<F: Font + ?Sized>(font: &F, c: char) -> Option<Glyph<'_>> {
+    font.glyph(c)
+        .or_else(|| font.glyph(REPLACEMENT_CHAR))
+        .or_else(|| font.glyph('?'))
+}
+
+/// One character, placed relative to the pen: the pen sits on the top
+/// of the line and moves right by `advance`
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub advance: usize,
+    pub bitmap: GlyphBitmap<'a>,
+}
+
+/// Glyph pixels, rows `stride` bytes apart, most significant bits first
+#[derive(Debug, Clone, Copy)]
+pub enum GlyphBitmap<'a> {
+    Mono { data: &'a [u8], stride: usize },
+    Alpha4 { data: &'a [u8], stride: usize },
+    Alpha8 { data: &'a [u8], stride: usize },
+}
+
+impl GlyphBitmap<'_> {
+    /// Alpha 0 to 255 at (x, y)
+    pub fn alpha(&self, x: usize, y: usize) -> u8 {
+        match *self {
+            GlyphBitmap::Mono { data, stride } => {
+                if data[y * stride + x / 8] & (0x80 >> (x % 8)) != 0 {
+                    255
+                } else {
+                    0
+                }
+            }
+            GlyphBitmap::Alpha4 { data, stride } => {
+                let shift = if x.is_multiple_of(2) { 4 } else { 0 };
+                ((data[y * stride + x / 2] >> shift) & 0x0F) * 17
+            }
+            GlyphBitmap::Alpha8 { data, stride } => data[y * stride + x],
+        }
+    }
+}
+
+/// Printable ASCII, plus the extended set from glyphs.rs
+impl<const N: usize> Font for FontTable<N> {
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let stride = self.width.div_ceil(8);
+        Some(Glyph {
+            width: self.width,
+            height: self.height,
+            x_offset: 0,
+            y_offset: 0,
+            advance: self.width,
+            bitmap: GlyphBitmap::Mono {
+                data: table_glyph(self, c)?,
+                stride,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaDepth {
+    Four,  // two pixels a byte, high nibble first
+    Eight, // one pixel a byte
+}
+
+impl AlphaDepth {
+    fn bits(self) -> usize {
+        match self {
+            AlphaDepth::Four => 4,
+            AlphaDepth::Eight => 8,
+        }
+    }
+}
+
+/// Fixed width font with an alpha value per pixel. Glyphs are stored one
+/// after the other from `first`, rows padded to whole bytes, then any
+/// glyphs outside that run listed in `extra`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaFont {
+    pub width: usize,
+    pub height: usize,
+    depth: AlphaDepth,
+    first: char,
+    count: usize, // glyphs from `first`
+    extra: HashMap<char, usize>,
+    table: Vec<u8>,
+}
+
+impl AlphaFont {
+    /// Glyph table converted elsewhere, None if `table` is not a whole
+    /// number of glyphs
+    pub fn from_raw(
+        width: usize,
+        height: usize,
+        depth: AlphaDepth,
+        first: char,
+        table: Vec<u8>,
+    ) -> Option<Self> {
+        let mut font = Self {
+            width,
+            height,
+            depth,
+            first,
+            count: 0,
+            extra: HashMap::new(),
+            table,
+        };
+        let glyph_size = font.glyph_size();
+        font.count = font.table.len().checked_div(glyph_size)?;
+        (glyph_size > 0 && font.table.len().is_multiple_of(glyph_size)).then_some(font)
+    }
+
+    /// Scale a 1-bit font down by `scale`, each alpha value is the share
+    /// of set pixels in a `scale` x `scale` block. The extended glyphs
+    /// come along.
+    pub fn from_font_table<const N: usize>(
+        font: &FontTable<N>,
+        scale: usize,
+        depth: AlphaDepth,
+    ) -> Self {
+        let scale = scale.max(1);
+        let src_row = font.width.div_ceil(8);
+        let ascii = N / (src_row * font.height);
+        let glyphs: Vec<(char, &[u8])> = (' '..='~')
+            .take(ascii)
+            .chain(extended_chars())
+            .filter_map(|c| Some((c, table_glyph(font, c)?)))
+            .collect();
+
+        let (width, height) = (font.width.div_ceil(scale), font.height.div_ceil(scale));
+        let mut alpha_font = Self {
+            width,
+            height,
+            depth,
+            first: ' ',
+            count: ascii,
+            extra: glyphs
+                .iter()
+                .enumerate()
+                .skip(ascii)
+                .map(|(g, &(c, _))| (c, g))
+                .collect(),
+            table: Vec::new(),
+        };
+        let row_bytes = alpha_font.row_bytes();
+        alpha_font.table = vec![0; glyphs.len() * row_bytes * height];
+
+        let max = (1u32 << depth.bits()) - 1;
+        for (g, (_, data)) in glyphs.iter().enumerate() {
+            let bit = |x: usize, y: usize| {
+                x < font.width
+                    && y < font.height
+                    && data[y * src_row + x / 8] & (0x80 >> (x % 8)) != 0
+            };
+            for y in 0..height {
+                for x in 0..width {
+                    let mut set = 0;
+                    for j in 0..scale {
+                        for i in 0..scale {
+                            set += bit(x * scale + i, y * scale + j) as u32;
+                        }
+                    }
+                    let value = (set * max + (scale * scale) as u32 / 2) / (scale * scale) as u32;
+                    let idx = (g * height + y) * row_bytes;
+                    alpha_font.put(idx, x, value as u8);
+                }
+            }
+        }
+        alpha_font
+    }
+
+    fn row_bytes(&self) -> usize {
+        (self.width * self.depth.bits()).div_ceil(8)
+    }
+
+    fn glyph_size(&self) -> usize {
+        self.row_bytes() * self.height
+    }
+
+    fn put(&mut self, row: usize, x: usize, value: u8) {
+        match self.depth {
+            AlphaDepth::Four => {
+                let shift = if x.is_multiple_of(2) { 4 } else { 0 };
+                self.table[row + x / 2] |= (value & 0x0F) << shift;
+            }
+            AlphaDepth::Eight => self.table[row + x] = value,
+        }
+    }
+}
+
+impl Font for AlphaFont {
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let size = self.glyph_size();
+        let index = match self.extra.get(&c) {
+            Some(&index) => index,
+            None => (c as usize)
+                .checked_sub(self.first as usize)
+                .filter(|&index| index < self.count)?,
+        };
+        let data = self.table.get(index * size..(index + 1) * size)?;
+        let stride = self.row_bytes();
+        Some(Glyph {
+            width: self.width,
+            height: self.height,
+            x_offset: 0,
+            y_offset: 0,
+            advance: self.width,
+            bitmap: match self.depth {
+                AlphaDepth::Four => GlyphBitmap::Alpha4 { data, stride },
+                AlphaDepth::Eight => GlyphBitmap::Alpha8 { data, stride },
+            },
+        })
+    }
+}
+
+impl<B: DisplayBus> Lcd<B> {
+    /// Draw `str` with the pen starting at (x, y), the top left of the
+    /// line. With `bg` the line is filled and each pixel written once, ink
+    /// over `bg`, so drawing the same text again changes nothing, otherwise
+    /// the glyphs are blended over the canvas. Characters the font lacks show
+    /// as the replacement glyph, or '?' if it has none. Pairs are kerned
+    /// if the font says so. Returns the pen position after the last
+    /// character.
+    pub fn img_draw_text<F: Font + ?Sized>(
+        &mut self,
+        x: i32,
+        y: i32,
+        str: &str,
+        font: &F,
+        colour: UWORD,
+        bg: Option<UWORD>,
+    ) -> i32 {
+        let mut pen = x;
+        let mut prev = None;
+        let mut glyphs = Vec::new();
+        for c in str.chars() {
+            let Some(glyph) = glyph_or_fallback(font, c) else {
+                continue;
+            };
+            if let Some(prev) = prev {
+                pen += font.kerning(prev, c);
+            }
+            glyphs.push((pen, glyph));
+            pen += glyph.advance as i32;
+            prev = Some(c);
+        }
+        match bg {
+            Some(bg) => self.img_draw_glyphs_on(x, y, pen, font.height(), &glyphs, colour, bg),
+            None => {
+                for (pen, glyph) in &glyphs {
+                    self.img_draw_glyph_bitmap(*pen, y, glyph, colour);
+                }
+            }
+        }
+        pen
+    }
+
+    fn img_draw_glyph_bitmap(&mut self, x: i32, y: i32, glyph: &Glyph, colour: UWORD) {
+        for j in 0..glyph.height {
+            for i in 0..glyph.width {
+                let (px, py) = (x + glyph.x_offset + i as i32, y + glyph.y_offset + j as i32);
+                if px >= 0 && py >= 0 {
+                    self.img_blend_pixel(
+                        px as usize,
+                        py as usize,
+                        colour,
+                        glyph.bitmap.alpha(i, j),
+                    );
+                }
+            }
+        }
+    }
+
+    // The line from `x` to `end` with the glyphs' ink over `bg`, each
+    // pixel written once even where kerning overlaps the cells. Ink
+    // outside the line is blended over the canvas.
+    #[allow(clippy::too_many_arguments)]
+    fn img_draw_glyphs_on(
+        &mut self,
+        x: i32,
+        y: i32,
+        end: i32,
+        height: usize,
+        glyphs: &[(i32, Glyph)],
+        colour: UWORD,
+        bg: UWORD,
+    ) {
+        let width = (end - x).max(0) as usize;
+        let mut cover = vec![0u8; width * height];
+        for (pen, glyph) in glyphs {
+            for j in 0..glyph.height {
+                for i in 0..glyph.width {
+                    let alpha = glyph.bitmap.alpha(i, j);
+                    if alpha == 0 {
+                        continue;
+                    }
+                    let (cx, cy) = (
+                        pen - x + glyph.x_offset + i as i32,
+                        glyph.y_offset + j as i32,
+                    );
+                    if (0..width as i32).contains(&cx) && (0..height as i32).contains(&cy) {
+                        let c = &mut cover[cy as usize * width + cx as usize];
+                        *c = (*c as u32 + alpha as u32 * (255 - *c as u32) / 255) as u8;
+                    } else if x + cx >= 0 && y + cy >= 0 {
+                        self.img_blend_pixel((x + cx) as usize, (y + cy) as usize, colour, alpha);
+                    }
+                }
+            }
+        }
+        for j in 0..height {
+            for i in 0..width {
+                let (px, py) = (x + i as i32, y + j as i32);
+                if px >= 0 && py >= 0 {
+                    let pixel = alpha_blend(colour, bg, cover[j * width + i]);
+                    self.img_draw_pixel(px as usize, py as usize, pixel);
+                }
+            }
+        }
+    }
+
+    // Rectangle with signed, clipped coordinates
+    pub(crate) fn img_fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, colour: UWORD) {
+        let (x0, y0) = (x.max(0), y.max(0));
+        let (x1, y1) = (x + w, y + h);
+        if x1 > x0 && y1 > y0 {
+            self.img_draw_rect(
+                x0 as usize,
+                y0 as usize,
+                (x1 - x0) as usize,
+                (y1 - y0) as usize,
+                colour,
+            );
+        }
+    }
+
+    /// Anti-aliased character, blended with `bg` or, if None, with the
+    /// pixels already on the canvas
+    pub fn img_draw_alpha_char(
+        &mut self,
+        x: i32,
+        y: i32,
+        c: char,
+        font: &AlphaFont,
+        colour: UWORD,
+        bg: Option<UWORD>,
+    ) -> &Self {
+        let mut buf = [0; 4];
+        self.img_draw_text(x, y, c.encode_utf8(&mut buf), font, colour, bg);
+        self
+    }
+
+    pub fn img_draw_alpha_string(
+        &mut self,
+        x: i32,
+        y: i32,
+        str: &str,
+        font: &AlphaFont,
+        colour: UWORD,
+        bg: Option<UWORD>,
+    ) -> &Self {
+        self.img_draw_text(x, y, str, font, colour, bg);
+        self
+    }
+}
@@ -0,0 +1,307 @@
+//! Text measurement and layout: `measure_text()`, proportional spacing
+//! and kerning for any `Font`, and helpers to align, truncate with an
+//! ellipsis or word wrap text within a box.
+//!
+//! Widths come from the glyph advances and kerning, the same numbers
+//! `img_draw_text()` uses, so measured text lands where it is drawn.
+//!
+//! typeset.rs
+//! Copyright (c) 2025 Vinodh Kumar Markapuram <GreenHex@gmail.com>
+//! 18-Jul-2025
+//!
+
+use crate::bus::*;
+use crate::defs::*;
+use crate::lcd::lcd::*;
+use crate::text::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const ELLIPSIS: char = '\u{2026}';
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    #[serde(alias = "center")]
+    Centre,
+    Right,
+}
+
+impl Align {
+    /// Offset of `width` wide text in a `box_width` wide box, text wider
+    /// than the box starts at its left edge
+    pub fn offset(self, width: usize, box_width: usize) -> usize {
+        let spare = box_width.saturating_sub(width);
+        match self {
+            Align::Left => 0,
+            Align::Centre => spare / 2,
+            Align::Right => spare,
+        }
+    }
+}
+
+/// Width and height in pixels of `str` on one line
+pub fn measure_text<F: Font + ?Sized>(str: &str, font: &F) -> (usize, usize) {
+    let mut width = 0i32;
+    let mut prev = None;
+    for c in str.chars() {
+        let Some(glyph) = glyph_or_fallback(font, c) else {
+            continue;
+        };
+        if let Some(prev) = prev {
+            width += font.kerning(prev, c);
+        }
+        width += glyph.advance as i32;
+        prev = Some(c);
+    }
+    (width.max(0) as usize, font.height())
+}
+
+/// `str` cut short with an ellipsis to fit in `max_width`, as is if it
+/// already fits
+pub fn ellipsize<F: Font + ?Sized>(str: &str, font: &F, max_width: usize) -> String {
+    if measure_text(str, font).0 <= max_width {
+        str.to_string()
+    } else {
+        truncate(str, font, max_width)
+    }
+}
+
+// As much of `str` as fits with an ellipsis after it, nothing if not even
+// the ellipsis fits
+fn truncate<F: Font + ?Sized>(str: &str, font: &F, max_width: usize) -> String {
+    // Fonts without one get three dots
+    let ellipsis = if font.glyph(ELLIPSIS).is_some() {
+        ELLIPSIS.to_string()
+    } else {
+        "...".to_string()
+    };
+    let with_ellipsis = |s: &str| format!("{}{}", s.trim_end(), ellipsis);
+    let mut fit = String::new();
+    for c in str.chars() {
+        fit.push(c);
+        if measure_text(&with_ellipsis(&fit), font).0 > max_width {
+            fit.pop();
+            break;
+        }
+    }
+    let fit = with_ellipsis(&fit);
+    if measure_text(&fit, font).0 <= max_width {
+        fit
+    } else {
+        String::new()
+    }
+}
+
+/// Split `str` into lines no wider than `max_width`, breaking at spaces
+/// and newlines. Words too long for a line of their own are broken
+/// wherever they have to be.
+pub fn wrap_text<F: Font + ?Sized>(str: &str, font: &F, max_width: usize) -> Vec<String> {
+    let fits = |s: &str| measure_text(s, font).0 <= max_width;
+    let mut lines = Vec::new();
+    for paragraph in str.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if fits(&candidate) {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Break the word, at least a character a line
+            for c in word.chars() {
+                line.push(c);
+                if !fits(&line) && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Any font with its glyphs trimmed to their ink and `spacing` pixels
+/// between them, so an 'i' takes less room than an 'm'. Spaces are half
+/// the font's own width. Pairs that leave a gap, like "To" or "AV", are
+/// kerned closer.
+#[derive(Debug, Clone)]
+pub struct Proportional<F> {
+    font: F,
+    spacing: usize,
+    profiles: RefCell<HashMap<char, Option<Profile>>>, // built on first use
+}
+
+// Leftmost and rightmost inked column of each line row, relative to the
+// pen of the proportional glyph
+type Profile = Rc<[Option<(i32, i32)>]>;
+
+impl<F: Font> Proportional<F> {
+    pub fn new(font: F) -> Self {
+        Self {
+            font,
+            spacing: 1,
+            profiles: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    fn profile(&self, c: char) -> Option<Profile> {
+        if let Some(profile) = self.profiles.borrow().get(&c) {
+            return profile.clone();
+        }
+        let profile = self.make_profile(c);
+        self.profiles.borrow_mut().insert(c, profile.clone());
+        profile
+    }
+
+    fn make_profile(&self, c: char) -> Option<Profile> {
+        let glyph = self.glyph(c)?;
+        let mut rows = vec![None; self.font.height()];
+        for j in 0..glyph.height {
+            let y = glyph.y_offset + j as i32;
+            let Some(row) = usize::try_from(y).ok().and_then(|y| rows.get_mut(y)) else {
+                continue;
+            };
+            for i in 0..glyph.width {
+                if glyph.bitmap.alpha(i, j) > 0 {
+                    let x = glyph.x_offset + i as i32;
+                    *row = Some(match *row {
+                        Some((l, r)) => (x.min(l), x.max(r)),
+                        None => (x, x),
+                    });
+                }
+            }
+        }
+        Some(rows.into())
+    }
+}
+
+impl<F: Font> Font for Proportional<F> {
+    fn height(&self) -> usize {
+        self.font.height()
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let glyph = self.font.glyph(c)?;
+        let inked = |i: usize| (0..glyph.height).any(|j| glyph.bitmap.alpha(i, j) > 0);
+        let Some(first) = (0..glyph.width).find(|&i| inked(i)) else {
+            return Some(Glyph {
+                advance: glyph.advance.div_ceil(2),
+                ..glyph
+            });
+        };
+        let last = (0..glyph.width).rfind(|&i| inked(i)).unwrap_or(first);
+        Some(Glyph {
+            x_offset: -(first as i32),
+            advance: last - first + 1 + self.spacing,
+            ..glyph
+        })
+    }
+
+    // Closest approach of the two glyphs on neighbouring rows, less the
+    // spacing, up to an eighth of the line height
+    fn kerning(&self, left: char, right: char) -> i32 {
+        let (Some(l), Some(r), Some(glyph)) =
+            (self.profile(left), self.profile(right), self.glyph(left))
+        else {
+            return self.font.kerning(left, right);
+        };
+        let advance = glyph.advance as i32;
+        let mut gap: Option<i32> = None;
+        for y in 0..l.len() {
+            let Some((_, l_right)) = l[y] else {
+                continue;
+            };
+            for &(r_left, _) in r[y.saturating_sub(1)..(y + 2).min(r.len())]
+                .iter()
+                .flatten()
+            {
+                let g = advance + r_left - l_right - 1;
+                gap = Some(gap.map_or(g, |gap| gap.min(g)));
+            }
+        }
+        let max = (self.font.height() / 8).max(1) as i32;
+        let tighten = gap.map_or(0, |gap| (gap - self.spacing as i32 - 1).clamp(0, max));
+        self.font.kerning(left, right) - tighten
+    }
+}
+
+impl<B: DisplayBus> Lcd<B> {
+    /// One line of text aligned in the `w` wide box at (x, y), cut short
+    /// with an ellipsis if too long. With `bg` the rest of the box is
+    /// filled around the text, each pixel once. Returns the pen position after the last character.
+    #[allow(clippy::too_many_arguments)]
+    pub fn img_draw_text_aligned<F: Font + ?Sized>(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: usize,
+        str: &str,
+        font: &F,
+        colour: UWORD,
+        bg: Option<UWORD>,
+        align: Align,
+    ) -> i32 {
+        let text = ellipsize(str, font, w);
+        let (width, _) = measure_text(&text, font);
+        let left = align.offset(width, w) as i32;
+        let pen = self.img_draw_text(x + left, y, &text, font, colour, bg);
+        if let Some(bg) = bg {
+            let height = font.height() as i32;
+            self.img_fill_rect(x, y, left, height, bg);
+            self.img_fill_rect(pen, y, x + w as i32 - pen, height, bg);
+        }
+        pen
+    }
+
+    /// Word wrapped text in the `w` x `h` box at (x, y), each line aligned
+    /// on its own. Lines that don't fit are dropped and the last one that
+    /// does gets an ellipsis. Returns the number of lines drawn.
+    #[allow(clippy::too_many_arguments)]
+    pub fn img_draw_text_wrapped<F: Font + ?Sized>(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: usize,
+        h: usize,
+        str: &str,
+        font: &F,
+        colour: UWORD,
+        bg: Option<UWORD>,
+        align: Align,
+    ) -> usize {
+        let mut lines = wrap_text(str, font, w);
+        let rows = h / font.height().max(1);
+        if lines.len() > rows {
+            lines.truncate(rows);
+            if let Some(last) = lines.last_mut() {
+                *last = truncate(last, font, w);
+            }
+        }
+        for (n, line) in lines.iter().enumerate() {
+            let y = y + (n * font.height()) as i32;
+            self.img_draw_text_aligned(x, y, w, line, font, colour, bg, align);
+        }
+        if let Some(bg) = bg {
+            let used = (lines.len() * font.height()) as i32;
+            self.img_fill_rect(x, y + used, w as i32, h as i32 - used, bg);
+        }
+        lines.len()
+    }
+}
@@ -5,6 +5,7 @@
 //! 04-Jun-2025
 //!
 
+use crate::config::*;
 use crate::defs::*;
 use crate::stats::*;
 use log::{LevelFilter, debug, error, info, warn};
@@ -26,10 +27,14 @@ const _CMD_ON: &str = ":ON:";
 
 const SPI_READ_BUFFER_SIZE: usize = 100_000;
 
-pub fn usb_thd(m: Arc<Mutex<bool>>, crypto_result: Arc<Mutex<CryptoResult>>) {
+/// Talk to the remote display, reconnecting if a reload selects another
+/// device.
+#[allow(clippy::collapsible_if)]
+pub fn usb_thd(config: SharedConfig, m: Arc<Mutex<bool>>, crypto_result: Arc<Mutex<CryptoResult>>) {
     'outer: loop {
+        let usb_config = config.lock().unwrap().usb.clone();
         if let Ok(ports) = available_ports() {
-            if let Ok(port_name) = get_port(ports) {
+            if let Ok(port_name) = get_port(ports, &usb_config) {
                 let builder = serialport::new(port_name.clone(), 115_200)
                     .stop_bits(StopBits::One)
                     .data_bits(DataBits::Eight)
@@ -74,6 +79,12 @@ pub fn usb_thd(m: Arc<Mutex<bool>>, crypto_result: Arc<Mutex<CryptoResult>>) {
                 }
 
                 'inner: while port.try_clone().unwrap().read_clear_to_send().unwrap() {
+                    if config.lock().unwrap().usb != usb_config {
+                        info!("{}(): USB device changed, reconnecting", func_name!());
+                        let _ = send_usb(port.try_clone().unwrap(), _CMD_OFF);
+                        break 'inner;
+                    }
+
                     match send_usb(port.try_clone().unwrap(), _CMD_READY) {
                         Ok(_) => {}
                         Err(_) => {
@@ -176,6 +187,7 @@ fn break_check(port: Box<dyn SerialPort + 'static>, m: Arc<Mutex<bool>>) -> bool
     retval
 }
 
+#[allow(clippy::to_string_in_format_args)]
 fn read_usb(mut port: Box<dyn SerialPort + 'static>, str: &str) -> Result<usize> {
     let mut read_buff: Vec<u8> = vec![0; SPI_READ_BUFFER_SIZE];
 
@@ -223,22 +235,47 @@ fn send_usb(mut port: Box<dyn SerialPort>, str: &str) -> Result<usize> {
     }
 }
 
-fn get_port(ports: Vec<SerialPortInfo>) -> Result<String> {
-    let found_port: Option<SerialPortInfo> = ports.into_iter().find(|info| {
-        if let SerialPortInfo {
-            port_type: SerialPortType::UsbPort(info),
-            ..
-        } = info
-        {
-            if info.vid == USB_DEV_VENDOR_ID && info.pid == USB_DEV_PRODUCT_ID {
-                info.serial_number == Some(String::from(USB_DEV_SERIAL_NUM))
-            } else {
-                false
+/// True if the port is the remote display selected in the config
+pub fn port_matches(port: &SerialPortInfo, config: &UsbConfig) -> bool {
+    if let SerialPortInfo {
+        port_type: SerialPortType::UsbPort(info),
+        ..
+    } = port
+    {
+        info.vid == config.vendor_id
+            && info.pid == config.product_id
+            && info.serial_number.as_ref() == Some(&config.serial_num)
+    } else {
+        false
+    }
+}
+
+/// All serial ports, one line each, matching ones marked with '*'
+pub fn usb_probe(config: &UsbConfig) -> Result<Vec<String>> {
+    Ok(available_ports()?
+        .iter()
+        .map(|port| {
+            let mark = if port_matches(port, config) { '*' } else { ' ' };
+            match &port.port_type {
+                SerialPortType::UsbPort(info) => format!(
+                    "{} {} {:04X}:{:04X} serial {} ({} {})",
+                    mark,
+                    port.port_name,
+                    info.vid,
+                    info.pid,
+                    info.serial_number.as_deref().unwrap_or("-"),
+                    info.manufacturer.as_deref().unwrap_or(""),
+                    info.product.as_deref().unwrap_or("")
+                ),
+                other => format!("{} {} {:?}", mark, port.port_name, other),
             }
-        } else {
-            false
-        }
-    });
+        })
+        .collect())
+}
+
+fn get_port(ports: Vec<SerialPortInfo>, config: &UsbConfig) -> Result<String> {
+    let found_port: Option<SerialPortInfo> =
+        ports.into_iter().find(|port| port_matches(port, config));
 
     if let Some(SerialPortInfo { port_name, .. }) = found_port {
         Ok(port_name)
@@ -51,7 +51,7 @@ pub fn get_cpu_info() -> (String, String, String, String) {
 
     let cpu_temperature: String = match sys.cpu_temp() {
         Ok(cpu_temp) => {
-            format!("{:.2}\"C", cpu_temp) // "deg" symbol => \u{00B0}
+            format!("{:.2}°C", cpu_temp)
         }
         Err(e) => {
             error!("{}(): Error reading CPU temperature: {}", func_name!(), e);